@@ -0,0 +1,186 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, bail, Context, Result};
+
+// Options that do not take a value.
+const FLAGS: &[&str] = &["dry-run", "reversible", "put", "all", "help"];
+
+// Command line of the form `so-cli [--option value | --flag]... <command>
+// [--option value | --flag]...`. Options can come before or after the
+// command. Options that are never read are reported so typos do not get
+// silently ignored.
+pub struct Args {
+    pub command: Option<String>,
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+    used: RefCell<HashSet<String>>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut command: Option<String> = None;
+        let mut values: HashMap<String, String> = HashMap::new();
+        let mut flags: HashSet<String> = HashSet::new();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                if FLAGS.contains(&name) {
+                    flags.insert(name.to_string());
+                    continue;
+                }
+                let value: String = args
+                    .next()
+                    .ok_or_else(|| anyhow!("--{} needs a value", name))?;
+                if values.insert(name.to_string(), value).is_some() {
+                    bail!("--{} given more than once", name);
+                }
+            } else if command.is_none() {
+                command = Some(arg);
+            } else {
+                bail!("unexpected argument {}", arg);
+            }
+        }
+
+        Ok(Args {
+            command,
+            values,
+            flags,
+            used: RefCell::new(HashSet::new()),
+        })
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.used.borrow_mut().insert(name.to_string());
+        self.flags.contains(name)
+    }
+
+    pub fn optional<T>(&self, name: &str) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.used.borrow_mut().insert(name.to_string());
+        match self.values.get(name) {
+            Some(value) => value
+                .parse::<T>()
+                .map(Some)
+                .map_err(|e| anyhow!("invalid --{} {}: {}", name, value, e)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn required<T>(&self, name: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.optional(name)?
+            .ok_or_else(|| anyhow!("missing --{}", name))
+    }
+
+    pub fn pubkey(&self, name: &str) -> Result<Pubkey> {
+        self.required(name)
+    }
+
+    pub fn optional_pubkey(&self, name: &str) -> Result<Option<Pubkey>> {
+        self.optional(name)
+    }
+
+    // Comma separated list, for example --prices 1.5,2,2.5.
+    pub fn list<T>(&self, name: &str) -> Result<Option<Vec<T>>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let value: Option<String> = self.optional(name)?;
+        value
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|item| {
+                        item.trim()
+                            .parse::<T>()
+                            .map_err(|e| anyhow!("invalid --{} item {}: {}", name, item, e))
+                    })
+                    .collect()
+            })
+            .transpose()
+            .with_context(|| format!("reading --{}", name))
+    }
+
+    // Call once the command has read everything it needs.
+    pub fn finish(&self) -> Result<()> {
+        let used = self.used.borrow();
+        let unknown: Vec<String> = self
+            .values
+            .keys()
+            .chain(self.flags.iter())
+            .filter(|name| !used.contains(*name))
+            .map(|name| format!("--{}", name))
+            .collect();
+        if !unknown.is_empty() {
+            bail!("unknown options for this command: {}", unknown.join(", "));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Args> {
+        Args::parse(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn options_before_and_after_the_command() {
+        let args: Args = parse("--dry-run issue --strike 5 --url http://x").unwrap();
+        assert_eq!(args.command.as_deref(), Some("issue"));
+        assert!(args.flag("dry-run"));
+        assert!(!args.flag("reversible"));
+        assert_eq!(args.required::<u64>("strike").unwrap(), 5);
+        assert_eq!(
+            args.optional::<String>("url").unwrap().as_deref(),
+            Some("http://x")
+        );
+        assert_eq!(args.optional::<u64>("amount").unwrap(), None);
+        args.finish().unwrap();
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse("issue --strike").is_err());
+        assert!(parse("issue --strike 1 --strike 2").is_err());
+        assert!(parse("issue extra").is_err());
+
+        let args: Args = parse("issue --strike x").unwrap();
+        assert!(args.required::<u64>("strike").is_err());
+        assert!(args.required::<u64>("amount").is_err());
+    }
+
+    #[test]
+    fn finish_reports_unread_options() {
+        let args: Args = parse("issue --strike 5 --stirke 6 --put").unwrap();
+        args.required::<u64>("strike").unwrap();
+        let error: String = args.finish().unwrap_err().to_string();
+        assert!(error.contains("--stirke"));
+        assert!(error.contains("--put"));
+        assert!(!error.contains("--strike"));
+    }
+
+    #[test]
+    fn lists() {
+        let args: Args = parse("init-strike --prices 1.5,2,2.5 --strikes 1,x").unwrap();
+        assert_eq!(
+            args.list::<String>("prices").unwrap(),
+            Some(vec!["1.5".to_string(), "2".to_string(), "2.5".to_string()])
+        );
+        assert!(args.list::<u64>("strikes").is_err());
+        assert_eq!(args.list::<u64>("amounts").unwrap(), None);
+    }
+}
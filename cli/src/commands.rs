@@ -0,0 +1,402 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_spl::token::Mint;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use staking_options::{ConfigParams, OptionType, State};
+use staking_options_client::units::price_to_strike;
+use staking_options_client::{
+    config_v4, fetch_account, fetch_state, inspect_state, pda, AccountFetcher, ConfigAccounts, So,
+};
+
+use crate::args::Args;
+
+pub enum Output {
+    // Sent in order, each as its own transaction.
+    Transactions(Vec<Vec<Instruction>>),
+    Json(Value),
+}
+
+// The signer pays for and signs everything.
+pub fn run<F: AccountFetcher>(
+    command: &str,
+    args: &Args,
+    signer: &Pubkey,
+    fetcher: &F,
+) -> Result<Output> {
+    match command {
+        "config" => config(args, signer),
+        "init-strike" => init_strike(args, signer, fetcher),
+        "issue" => issue(args, signer, fetcher),
+        "exercise" => exercise(args, signer, fetcher),
+        "add-tokens" => add_tokens(args, signer, fetcher),
+        "name-token" => name_token(args, signer, fetcher),
+        "withdraw" => withdraw(args, signer, fetcher),
+        "state" => state(args, fetcher),
+        "inspect" => inspect(args, fetcher),
+        _ => bail!("unknown command {}", command),
+    }
+}
+
+fn load_so<F: AccountFetcher>(args: &Args, fetcher: &F) -> Result<So> {
+    let address: Pubkey = args.pubkey("state")?;
+    Ok(So::new(address, fetch_state(fetcher, &address)?))
+}
+
+// Creates the associated token account if it does not exist yet.
+fn create_ata(payer: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> Instruction {
+    create_associated_token_account_idempotent(payer, owner, mint, &anchor_spl::token::ID)
+}
+
+// Token accounts default to the signer's associated token account, which is
+// created if needed. Accounts given explicitly must already exist.
+fn token_account(
+    args: &Args,
+    name: &str,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    instructions: &mut Vec<Instruction>,
+) -> Result<Pubkey> {
+    match args.optional_pubkey(name)? {
+        Some(account) => Ok(account),
+        None => {
+            instructions.push(create_ata(signer, signer, mint));
+            Ok(pda::associated_token(signer, mint))
+        }
+    }
+}
+
+fn config(args: &Args, signer: &Pubkey) -> Result<Output> {
+    let base_mint: Pubkey = args.pubkey("base-mint")?;
+    let quote_mint: Pubkey = args.pubkey("quote-mint")?;
+    let params: ConfigParams = ConfigParams {
+        option_expiration: args.required("expiration")?,
+        subscription_period_end: args.required("subscription-end")?,
+        num_tokens: args.required("num-tokens")?,
+        lot_size: args.required("lot-size")?,
+        so_name: args.required("so-name")?,
+        reversible: args.flag("reversible"),
+        exercise_start: args.optional("exercise-start")?.unwrap_or(0),
+        option_decimals: args.optional("option-decimals")?.unwrap_or(0),
+        option_type: if args.flag("put") {
+            OptionType::Put
+        } else {
+            OptionType::Call
+        },
+    };
+
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let accounts: ConfigAccounts = ConfigAccounts {
+        authority: *signer,
+        so_authority: args.optional_pubkey("so-authority")?.unwrap_or(*signer),
+        issue_authority: args.optional_pubkey("issue-authority")?,
+        base_account: token_account(args, "base-account", signer, &base_mint, &mut instructions)?,
+        quote_account: token_account(
+            args,
+            "quote-account",
+            signer,
+            &quote_mint,
+            &mut instructions,
+        )?,
+        base_mint,
+        quote_mint,
+    };
+    eprintln!("state: {}", pda::state(&params.so_name, &base_mint));
+
+    instructions.push(config_v4(&accounts, params));
+    Ok(Output::Transactions(vec![instructions]))
+}
+
+// Strikes are given in quote atoms per lot with --strikes, or as the price of
+// a whole base token with --prices. More than one makes a ladder, with one
+// transaction per strike.
+fn init_strike<F: AccountFetcher>(args: &Args, signer: &Pubkey, fetcher: &F) -> Result<Output> {
+    let so: So = load_so(args, fetcher)?;
+    let reversible: bool = args.flag("reversible");
+
+    let strikes: Vec<u64> = match (args.list::<u64>("strikes")?, args.list::<String>("prices")?) {
+        (Some(strikes), None) => strikes,
+        (None, Some(prices)) => {
+            let quote_decimals: u8 = match args.optional("quote-decimals")? {
+                Some(decimals) => decimals,
+                None => fetch_account::<Mint>(fetcher, &so.state.quote_mint)?.decimals,
+            };
+            prices
+                .iter()
+                .map(|price| {
+                    price_to_strike(
+                        price,
+                        so.state.lot_size,
+                        so.state.base_decimals,
+                        quote_decimals,
+                    )
+                })
+                .collect::<Result<Vec<u64>, _>>()?
+        }
+        _ => bail!("give exactly one of --strikes or --prices"),
+    };
+    if strikes.contains(&0) {
+        bail!("strike rounds to zero quote atoms per lot");
+    }
+
+    Ok(Output::Transactions(
+        strikes
+            .iter()
+            .map(|strike| {
+                eprintln!("strike {}: option mint {}", strike, so.option_mint(*strike));
+                vec![if reversible {
+                    so.init_strike_reversible(signer, signer, *strike)
+                } else {
+                    so.init_strike_with_payer(signer, signer, *strike)
+                }]
+            })
+            .collect(),
+    ))
+}
+
+fn issue<F: AccountFetcher>(args: &Args, signer: &Pubkey, fetcher: &F) -> Result<Output> {
+    let so: So = load_so(args, fetcher)?;
+    let strike: u64 = args.required("strike")?;
+    let amount: u64 = args.required("amount")?;
+    let recipient: Pubkey = args.optional_pubkey("recipient")?.unwrap_or(*signer);
+
+    Ok(Output::Transactions(vec![vec![
+        create_ata(signer, &recipient, &so.option_mint(strike)),
+        so.issue(
+            signer,
+            &so.option_account(&recipient, strike),
+            amount,
+            strike,
+        ),
+    ]]))
+}
+
+// Exercises from the signer's associated token accounts. Creates the account
+// that receives the underlying.
+fn exercise<F: AccountFetcher>(args: &Args, signer: &Pubkey, fetcher: &F) -> Result<Output> {
+    let so: So = load_so(args, fetcher)?;
+    let strike: u64 = args.required("strike")?;
+    let amount: u64 = args.required("amount")?;
+
+    Ok(Output::Transactions(vec![match so.state.option_type {
+        OptionType::Call => vec![
+            create_ata(signer, signer, &so.state.base_mint),
+            so.exercise(signer, amount, strike),
+        ],
+        OptionType::Put => vec![
+            create_ata(signer, signer, &so.state.quote_mint),
+            so.exercise_put(signer, amount, strike),
+        ],
+    }]))
+}
+
+// Calls are funded with base and puts with quote, from the signer's
+// associated token account unless given.
+fn add_tokens<F: AccountFetcher>(args: &Args, signer: &Pubkey, fetcher: &F) -> Result<Output> {
+    let so: So = load_so(args, fetcher)?;
+    let amount: u64 = args.required("amount")?;
+
+    Ok(Output::Transactions(vec![vec![
+        match so.state.option_type {
+            OptionType::Call => {
+                let base_account: Pubkey = args
+                    .optional_pubkey("base-account")?
+                    .unwrap_or_else(|| so.base_account(signer));
+                so.add_tokens(signer, &base_account, amount)
+            }
+            OptionType::Put => {
+                let quote_account: Pubkey = args
+                    .optional_pubkey("quote-account")?
+                    .unwrap_or_else(|| so.quote_account(signer));
+                so.add_tokens_put(signer, &quote_account, amount)
+            }
+        },
+    ]]))
+}
+
+fn name_token<F: AccountFetcher>(args: &Args, signer: &Pubkey, fetcher: &F) -> Result<Output> {
+    let so: So = load_so(args, fetcher)?;
+    let strike: u64 = args.required("strike")?;
+
+    Ok(Output::Transactions(vec![vec![
+        so.name_token(signer, signer, strike)
+    ]]))
+}
+
+// Puts always use withdraw_put. For calls --all also takes the quote tokens
+// from reversible exercise.
+fn withdraw<F: AccountFetcher>(args: &Args, signer: &Pubkey, fetcher: &F) -> Result<Output> {
+    let so: So = load_so(args, fetcher)?;
+    let all: bool = args.flag("all");
+    let mut instructions: Vec<Instruction> = Vec::new();
+
+    match so.state.option_type {
+        OptionType::Put => {
+            let quote_account: Pubkey = token_account(
+                args,
+                "quote-account",
+                signer,
+                &so.state.quote_mint,
+                &mut instructions,
+            )?;
+            instructions.push(so.withdraw_put(signer, &quote_account));
+        }
+        OptionType::Call => {
+            let base_account: Pubkey = token_account(
+                args,
+                "base-account",
+                signer,
+                &so.state.base_mint,
+                &mut instructions,
+            )?;
+            if all {
+                let quote_account: Pubkey = token_account(
+                    args,
+                    "quote-account",
+                    signer,
+                    &so.state.quote_mint,
+                    &mut instructions,
+                )?;
+                instructions.push(so.withdraw_all(signer, &base_account, &quote_account));
+            } else {
+                instructions.push(so.withdraw(signer, &base_account));
+            }
+        }
+    }
+    Ok(Output::Transactions(vec![instructions]))
+}
+
+fn state<F: AccountFetcher>(args: &Args, fetcher: &F) -> Result<Output> {
+    let address: Pubkey = args.pubkey("state")?;
+    let state: State = fetch_state(fetcher, &address)?;
+
+    Ok(Output::Json(json!({
+        "address": address.to_string(),
+        "so_name": state.so_name,
+        "authority": state.authority.to_string(),
+        "issue_authority": state.issue_authority.to_string(),
+        "option_type": format!("{:?}", state.option_type),
+        "base_mint": state.base_mint.to_string(),
+        "quote_mint": state.quote_mint.to_string(),
+        "quote_account": state.quote_account.to_string(),
+        "options_available": state.options_available,
+        "lot_size": state.lot_size,
+        "base_decimals": state.base_decimals,
+        "option_decimals": state.option_decimals,
+        "subscription_period_end": state.subscription_period_end,
+        "option_expiration": state.option_expiration,
+        "exercise_start": state.exercise_start,
+        "strikes": state.strikes,
+        "deal_type": format!("{:?}", state.deal_type),
+        "oracle": state.oracle.to_string(),
+        "oracle_max_age_sec": state.oracle_max_age_sec,
+        "net_exercise_haircut_bps": state.net_exercise_haircut_bps,
+        "options_committed": state.options_committed,
+        "version": state.version,
+    })))
+}
+
+// State in human units with vault balances, for reports. --now overrides the
+// time used for time to expiration.
+fn inspect<F: AccountFetcher>(args: &Args, fetcher: &F) -> Result<Output> {
+    let address: Pubkey = args.pubkey("state")?;
+    let now: u64 = match args.optional("now")? {
+        Some(now) => now,
+        None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    };
+
+    Ok(Output::Json(serde_json::to_value(inspect_state(
+        fetcher, &address, now,
+    )?)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountDeserialize, AccountSerialize};
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+
+    // A call SO with 9 decimal base in lots of one token.
+    fn fetcher(address: &Pubkey) -> HashMap<Pubkey, Vec<u8>> {
+        let mut state: State =
+            State::try_deserialize_unchecked(&mut &vec![0u8; State::SPACE][..]).unwrap();
+        state.so_name = "TEST".to_string();
+        state.lot_size = 1_000_000_000;
+        state.base_decimals = 9;
+        let mut data: Vec<u8> = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+        HashMap::from([(*address, data)])
+    }
+
+    fn run_line(line: &str, fetcher: &HashMap<Pubkey, Vec<u8>>) -> Result<Output> {
+        let args: Args = Args::parse(line.split_whitespace().map(String::from))?;
+        let output: Output = run(
+            args.command.as_deref().unwrap(),
+            &args,
+            &Pubkey::new_unique(),
+            fetcher,
+        )?;
+        args.finish()?;
+        Ok(output)
+    }
+
+    fn strikes(output: Output) -> Vec<u64> {
+        match output {
+            Output::Transactions(transactions) => transactions
+                .iter()
+                .map(|instructions| {
+                    assert_eq!(instructions.len(), 1);
+                    // The strike is the only argument, after the discriminator.
+                    u64::from_le_bytes(instructions[0].data[8..16].try_into().unwrap())
+                })
+                .collect(),
+            Output::Json(_) => panic!("expected transactions"),
+        }
+    }
+
+    #[test]
+    fn init_strike_prices_ladder() {
+        let state: Pubkey = Pubkey::new_unique();
+        let fetcher = fetcher(&state);
+        let output: Output = run_line(
+            &format!(
+                "init-strike --state {} --prices 1.5,2,0.000001 --quote-decimals 6",
+                state
+            ),
+            &fetcher,
+        )
+        .unwrap();
+        assert_eq!(strikes(output), vec![1_500_000, 2_000_000, 1]);
+    }
+
+    #[test]
+    fn init_strike_rejects_bad_prices() {
+        let state: Pubkey = Pubkey::new_unique();
+        let fetcher = fetcher(&state);
+        for prices in ["1e3", "-1", "0.0000001", "99999999999999999999"] {
+            assert!(run_line(
+                &format!(
+                    "init-strike --state {} --prices {} --quote-decimals 6",
+                    state, prices
+                ),
+                &fetcher,
+            )
+            .is_err());
+        }
+        assert!(run_line(
+            &format!("init-strike --state {} --strikes 1 --prices 1", state),
+            &fetcher,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn state_not_found() {
+        let fetcher = fetcher(&Pubkey::new_unique());
+        assert!(run_line(&format!("state --state {}", Pubkey::new_unique()), &fetcher).is_err());
+    }
+}
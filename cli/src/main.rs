@@ -0,0 +1,215 @@
+// Command line tool for operating staking options. Builds instructions with
+// staking-options-client and sends them to an RPC, or prints them with
+// --dry-run.
+
+mod args;
+mod commands;
+mod rpc;
+
+use std::path::PathBuf;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use solana_sdk::message::Message;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use staking_options_client::{AccountFetcher, ClientError};
+
+use crate::args::Args;
+use crate::commands::Output;
+use crate::rpc::{AccountsDir, Rpc};
+
+const DEFAULT_URL: &str = "http://127.0.0.1:8899";
+
+const USAGE: &str = "\
+so-cli [OPTIONS] <COMMAND> [COMMAND OPTIONS]
+
+Options:
+  --url <URL>             JSON RPC url [default: http://127.0.0.1:8899]
+  --keypair <PATH>        Signer and fee payer [default: ~/.config/solana/id.json]
+  --accounts-dir <DIR>    Read accounts from <ADDRESS>.json files saved with
+                          `solana account --output json` instead of the RPC
+  --dry-run               Print the transactions instead of sending them
+  --output <FILE>         Write JSON output of state and inspect to a file
+
+Commands:
+  config       --base-mint <MINT> --quote-mint <MINT> --so-name <NAME>
+               --num-tokens <ATOMS> --lot-size <ATOMS> --expiration <UNIX>
+               --subscription-end <UNIX> [--exercise-start <UNIX>]
+               [--option-decimals <N>] [--so-authority <KEY>]
+               [--issue-authority <KEY>] [--base-account <KEY>]
+               [--quote-account <KEY>] [--reversible] [--put]
+  init-strike  --state <KEY> (--strikes <S1,S2..> | --prices <P1,P2..>)
+               [--quote-decimals <N>] [--reversible]
+  issue        --state <KEY> --strike <STRIKE> --amount <LOTS> [--recipient <KEY>]
+  exercise     --state <KEY> --strike <STRIKE> --amount <LOTS>
+  add-tokens   --state <KEY> --amount <ATOMS> [--base-account <KEY>]
+               [--quote-account <KEY>]
+  name-token   --state <KEY> --strike <STRIKE>
+  withdraw     --state <KEY> [--all] [--base-account <KEY>] [--quote-account <KEY>]
+  state        --state <KEY>
+  inspect      --state <KEY> [--now <UNIX>]
+               State in human units, vault balances against outstanding
+               options, and derived addresses
+
+Strikes are quote atoms per lot. Prices are quote tokens per whole base token.
+Token accounts default to the signer's associated token accounts.
+";
+
+enum Fetcher {
+    Rpc(Rpc),
+    Dir(AccountsDir),
+}
+
+impl AccountFetcher for Fetcher {
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        match self {
+            Fetcher::Rpc(rpc) => rpc.fetch_account_data(address),
+            Fetcher::Dir(dir) => dir.fetch_account_data(address),
+        }
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        prefix: &[u8],
+    ) -> Result<Option<Vec<(Pubkey, Vec<u8>)>>, ClientError> {
+        match self {
+            Fetcher::Rpc(rpc) => rpc.fetch_program_accounts(prefix),
+            Fetcher::Dir(dir) => dir.fetch_program_accounts(prefix),
+        }
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args: Args = Args::parse(std::env::args().skip(1))?;
+    let command: String = match (&args.command, args.flag("help")) {
+        (Some(command), false) => command.clone(),
+        _ => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+
+    let url: String = args
+        .optional("url")?
+        .unwrap_or_else(|| DEFAULT_URL.to_string());
+    let keypair_path: PathBuf = match args.optional::<String>("keypair")? {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(std::env::var("HOME")?).join(".config/solana/id.json"),
+    };
+    let keypair: Keypair = read_keypair_file(&keypair_path)
+        .map_err(|e| anyhow!("reading keypair {}: {}", keypair_path.display(), e))?;
+    let dry_run: bool = args.flag("dry-run");
+    let output_path: Option<String> = args.optional("output")?;
+
+    let fetcher: Fetcher = match args.optional::<String>("accounts-dir")? {
+        Some(dir) => Fetcher::Dir(AccountsDir::new(PathBuf::from(dir))),
+        None => Fetcher::Rpc(Rpc::new(&url)),
+    };
+
+    let output: Output = commands::run(&command, &args, &keypair.pubkey(), &fetcher)?;
+    args.finish()?;
+
+    match output {
+        Output::Json(value) => {
+            let printed: String = serde_json::to_string_pretty(&value)?;
+            match output_path {
+                Some(path) => std::fs::write(path, printed + "\n")?,
+                None => println!("{}", printed),
+            }
+        }
+        Output::Transactions(transactions) if dry_run => {
+            let printed: Vec<Value> = transactions
+                .iter()
+                .map(|instructions| transaction_json(instructions, &keypair.pubkey()))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&printed)?);
+        }
+        Output::Transactions(transactions) => {
+            let rpc: Rpc = Rpc::new(&url);
+            for instructions in transactions {
+                let mut transaction: Transaction =
+                    Transaction::new_with_payer(&instructions, Some(&keypair.pubkey()));
+                transaction.try_sign(&[&keypair], rpc.get_latest_blockhash()?)?;
+                println!("{}", rpc.send_and_confirm(&transaction)?);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Unsigned and without a recent blockhash, so it can be built offline. The
+// message can be signed elsewhere after setting the blockhash.
+fn transaction_json(instructions: &[Instruction], payer: &Pubkey) -> Value {
+    let message: Message = Message::new(instructions, Some(payer));
+    json!({
+        "instructions": instructions
+            .iter()
+            .map(|instruction| json!({
+                "program_id": instruction.program_id.to_string(),
+                "accounts": instruction
+                    .accounts
+                    .iter()
+                    .map(|meta| json!({
+                        "pubkey": meta.pubkey.to_string(),
+                        "is_signer": meta.is_signer,
+                        "is_writable": meta.is_writable,
+                    }))
+                    .collect::<Vec<Value>>(),
+                "data": base64::encode(&instruction.data),
+            }))
+            .collect::<Vec<Value>>(),
+        "message": base64::encode(message.serialize()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::instruction::AccountMeta;
+
+    #[test]
+    fn dry_run_transaction_json() {
+        let payer: Pubkey = Pubkey::new_unique();
+        let account: Pubkey = Pubkey::new_unique();
+        let instruction: Instruction = Instruction {
+            program_id: staking_options::ID,
+            accounts: vec![
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(account, false),
+            ],
+            data: vec![1, 2, 3],
+        };
+
+        // The message is the unsigned message for the payer, so it can be
+        // signed elsewhere.
+        let message: String = base64::encode(
+            Message::new(std::slice::from_ref(&instruction), Some(&payer)).serialize(),
+        );
+
+        let printed: Value = transaction_json(&[instruction], &payer);
+        let printed_instruction: &Value = &printed["instructions"][0];
+        assert_eq!(
+            printed_instruction["program_id"],
+            staking_options::ID.to_string()
+        );
+        assert_eq!(
+            printed_instruction["accounts"][0]["pubkey"],
+            payer.to_string()
+        );
+        assert_eq!(printed_instruction["accounts"][0]["is_signer"], true);
+        assert_eq!(printed_instruction["accounts"][1]["is_writable"], false);
+        assert_eq!(printed_instruction["data"], base64::encode([1, 2, 3]));
+        assert_eq!(printed["message"], message);
+    }
+}
@@ -0,0 +1,209 @@
+use std::path::PathBuf;
+use std::thread::sleep;
+use std::time::Duration;
+
+use anchor_lang::prelude::Pubkey;
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::{json, Value};
+use solana_sdk::bs58;
+use solana_sdk::hash::Hash;
+use solana_sdk::transaction::Transaction;
+use staking_options_client::{AccountFetcher, ClientError};
+
+// Seconds to wait for a sent transaction to be confirmed.
+const CONFIRM_TIMEOUT_SEC: u64 = 60;
+
+// JSON RPC over http or https. solana-client is not used since every 1.15
+// release is yanked and 1.16 does not build with anchor-spl 0.27.
+pub struct Rpc {
+    url: String,
+}
+
+impl Rpc {
+    pub fn new(url: &str) -> Self {
+        Rpc {
+            url: url.to_string(),
+        }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let body: String = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        })
+        .to_string();
+
+        let response: String = ureq::post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&body)
+            .with_context(|| format!("calling {} on {}", method, self.url))?
+            .into_string()?;
+
+        let response: Value = serde_json::from_str(&response)
+            .with_context(|| format!("parsing {} response", method))?;
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error);
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| anyhow!("{} returned no result", method))
+    }
+
+    pub fn get_account_data(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        let result: Value = self.call(
+            "getAccountInfo",
+            json!([address.to_string(), {"encoding": "base64"}]),
+        )?;
+        match &result["value"] {
+            Value::Null => Ok(None),
+            account => Ok(Some(decode_account_data(account)?)),
+        }
+    }
+
+    // Accounts of the program whose data starts with prefix.
+    pub fn get_program_accounts(&self, prefix: &[u8]) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let result: Value = self.call(
+            "getProgramAccounts",
+            json!([
+                staking_options::ID.to_string(),
+                {
+                    "encoding": "base64",
+                    "filters": [{"memcmp": {"offset": 0, "bytes": bs58::encode(prefix).into_string()}}],
+                },
+            ]),
+        )?;
+        result
+            .as_array()
+            .ok_or_else(|| anyhow!("getProgramAccounts returned no accounts"))?
+            .iter()
+            .map(|account| {
+                let address: Pubkey = account["pubkey"]
+                    .as_str()
+                    .ok_or_else(|| anyhow!("getProgramAccounts returned no pubkey"))?
+                    .parse()
+                    .map_err(|e| anyhow!("invalid pubkey: {}", e))?;
+                Ok((address, decode_account_data(&account["account"])?))
+            })
+            .collect()
+    }
+
+    pub fn get_latest_blockhash(&self) -> Result<Hash> {
+        let result: Value = self.call("getLatestBlockhash", json!([]))?;
+        result["value"]["blockhash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("getLatestBlockhash returned no blockhash"))?
+            .parse()
+            .map_err(|e| anyhow!("invalid blockhash: {}", e))
+    }
+
+    // Sends and waits for confirmation, so transactions that depend on each
+    // other can be sent one after another.
+    pub fn send_and_confirm(&self, transaction: &Transaction) -> Result<String> {
+        let serialized: String = base64::encode(bincode::serialize(transaction)?);
+        let signature: String = self
+            .call(
+                "sendTransaction",
+                json!([serialized, {"encoding": "base64"}]),
+            )?
+            .as_str()
+            .ok_or_else(|| anyhow!("sendTransaction returned no signature"))?
+            .to_string();
+
+        for _ in 0..CONFIRM_TIMEOUT_SEC {
+            let result: Value = self.call(
+                "getSignatureStatuses",
+                json!([[signature], {"searchTransactionHistory": false}]),
+            )?;
+            let status: &Value = &result["value"][0];
+            if !status.is_null() {
+                if !status["err"].is_null() {
+                    bail!("transaction {} failed: {}", signature, status["err"]);
+                }
+                let confirmation: &str = status["confirmationStatus"].as_str().unwrap_or("");
+                if confirmation == "confirmed" || confirmation == "finalized" {
+                    return Ok(signature);
+                }
+            }
+            sleep(Duration::from_secs(1));
+        }
+        bail!("transaction {} was not confirmed", signature)
+    }
+}
+
+impl AccountFetcher for Rpc {
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        self.get_account_data(address)
+            .map_err(|e| ClientError::Rpc(e.to_string()))?
+            .ok_or(ClientError::AccountNotFound(*address))
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        prefix: &[u8],
+    ) -> Result<Option<Vec<(Pubkey, Vec<u8>)>>, ClientError> {
+        self.get_program_accounts(prefix)
+            .map(Some)
+            .map_err(|e| ClientError::Rpc(e.to_string()))
+    }
+}
+
+// Accounts saved with `solana account <ADDRESS> --output json --output-file
+// <ADDRESS>.json`, the same format as the test fixtures. Lets commands that
+// read accounts run without a validator.
+pub struct AccountsDir {
+    dir: PathBuf,
+}
+
+impl AccountsDir {
+    pub fn new(dir: PathBuf) -> Self {
+        AccountsDir { dir }
+    }
+}
+
+impl AccountFetcher for AccountsDir {
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        let path: PathBuf = self.dir.join(format!("{}.json", address));
+        let contents: String =
+            std::fs::read_to_string(&path).map_err(|_| ClientError::AccountNotFound(*address))?;
+        let saved: Value = serde_json::from_str(&contents)
+            .map_err(|e| ClientError::InvalidAccountData(*address, e.to_string()))?;
+        decode_account_data(&saved["account"])
+            .map_err(|e| ClientError::InvalidAccountData(*address, e.to_string()))
+    }
+
+    // Every saved account is checked, so strikes missing from the State are
+    // found as long as their StrikeState was saved too.
+    fn fetch_program_accounts(
+        &self,
+        prefix: &[u8],
+    ) -> Result<Option<Vec<(Pubkey, Vec<u8>)>>, ClientError> {
+        let entries = std::fs::read_dir(&self.dir).map_err(|e| ClientError::Rpc(e.to_string()))?;
+        let mut accounts: Vec<(Pubkey, Vec<u8>)> = Vec::new();
+        for entry in entries {
+            let path: PathBuf = entry.map_err(|e| ClientError::Rpc(e.to_string()))?.path();
+            let address: Option<Pubkey> = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|name| name.parse().ok());
+            if let Some(address) = address {
+                let data: Vec<u8> = self.fetch_account_data(&address)?;
+                if data.starts_with(prefix) {
+                    accounts.push((address, data));
+                }
+            }
+        }
+        Ok(Some(accounts))
+    }
+}
+
+// Reads the data of an account encoded as ["<base64>", "base64"].
+fn decode_account_data(account: &Value) -> Result<Vec<u8>> {
+    let data: &str = account["data"][0]
+        .as_str()
+        .ok_or_else(|| anyhow!("account has no base64 data"))?;
+    Ok(base64::decode(data)?)
+}
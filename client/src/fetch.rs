@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use staking_options::{State, StrikeState};
+
+#[derive(Debug)]
+pub enum ClientError {
+    AccountNotFound(Pubkey),
+    // Error from whatever is used to reach the cluster.
+    Rpc(String),
+    // Account exists but is not the expected type.
+    InvalidAccountData(Pubkey, String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::AccountNotFound(address) => write!(f, "account {} not found", address),
+            ClientError::Rpc(message) => write!(f, "rpc error: {}", message),
+            ClientError::InvalidAccountData(address, message) => {
+                write!(f, "invalid data in account {}: {}", address, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+// Source of account data. Implemented for a map of accounts and for closures
+// so callers can plug in whichever RPC client they use, for example
+// |address| rpc.get_account_data(address).map_err(|e| ClientError::Rpc(e.to_string())).
+pub trait AccountFetcher {
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError>;
+
+    // Addresses and data of the program's accounts whose data starts with
+    // prefix. None when the source can only fetch by address, as closures do.
+    fn fetch_program_accounts(
+        &self,
+        _prefix: &[u8],
+    ) -> Result<Option<Vec<(Pubkey, Vec<u8>)>>, ClientError> {
+        Ok(None)
+    }
+}
+
+impl<F> AccountFetcher for F
+where
+    F: Fn(&Pubkey) -> Result<Vec<u8>, ClientError>,
+{
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        self(address)
+    }
+}
+
+impl AccountFetcher for HashMap<Pubkey, Vec<u8>> {
+    fn fetch_account_data(&self, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+        self.get(address)
+            .cloned()
+            .ok_or(ClientError::AccountNotFound(*address))
+    }
+
+    fn fetch_program_accounts(
+        &self,
+        prefix: &[u8],
+    ) -> Result<Option<Vec<(Pubkey, Vec<u8>)>>, ClientError> {
+        Ok(Some(
+            self.iter()
+                .filter(|(_, data)| data.starts_with(prefix))
+                .map(|(address, data)| (*address, data.clone()))
+                .collect(),
+        ))
+    }
+}
+
+// Checks the discriminator, so data from a different account type is
+// rejected instead of misread.
+pub fn deserialize_account<T: AccountDeserialize>(
+    address: &Pubkey,
+    data: &[u8],
+) -> Result<T, ClientError> {
+    let mut data: &[u8] = data;
+    T::try_deserialize(&mut data)
+        .map_err(|e| ClientError::InvalidAccountData(*address, e.to_string()))
+}
+
+pub fn fetch_account<T: AccountDeserialize>(
+    fetcher: &impl AccountFetcher,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    deserialize_account(address, &fetcher.fetch_account_data(address)?)
+}
+
+pub fn fetch_state(fetcher: &impl AccountFetcher, address: &Pubkey) -> Result<State, ClientError> {
+    fetch_account(fetcher, address)
+}
+
+pub fn fetch_strike_state(
+    fetcher: &impl AccountFetcher,
+    state: &Pubkey,
+    strike: u64,
+) -> Result<StrikeState, ClientError> {
+    fetch_account(fetcher, &crate::pda::strike_state(state, strike))
+}
+
+// Every StrikeState of an SO, including strikes past the ones State lists.
+// StrikeState data starts with the discriminator and the State address, so
+// they are matched on that. None when the fetcher cannot list accounts.
+pub fn fetch_strike_states(
+    fetcher: &impl AccountFetcher,
+    state: &Pubkey,
+) -> Result<Option<Vec<StrikeState>>, ClientError> {
+    let mut prefix: Vec<u8> = StrikeState::discriminator().to_vec();
+    prefix.extend_from_slice(&state.to_bytes());
+    match fetcher.fetch_program_accounts(&prefix)? {
+        Some(accounts) => accounts
+            .iter()
+            .map(|(address, data)| deserialize_account(address, data))
+            .collect::<Result<_, _>>()
+            .map(Some),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,525 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::token::{Mint, TokenAccount};
+use serde::Serialize;
+use staking_options::{OptionType, State, MAX_MONITORED_STRIKES};
+
+use crate::fetch::{fetch_account, fetch_state, fetch_strike_states, AccountFetcher, ClientError};
+use crate::pda;
+use crate::units::{atoms_to_tokens, strike_to_price};
+
+// Report of an SO in human units, for treasury reports and audits. Token
+// amounts carry both atoms and whole tokens, strikes carry both quote atoms
+// per lot and quote tokens per whole base token. Addresses are base58 so the
+// report serializes to plain JSON.
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Amount {
+    pub atoms: u64,
+    pub tokens: f64,
+}
+
+impl Amount {
+    pub fn new(atoms: u64, decimals: u8) -> Self {
+        Amount {
+            atoms,
+            tokens: atoms_to_tokens(atoms, decimals),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StateReport {
+    pub address: String,
+    pub so_name: String,
+    pub option_type: String,
+    pub authority: String,
+    pub issue_authority: String,
+    pub base_mint: String,
+    pub quote_mint: String,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    pub lot_size: Amount,
+    // In base for calls and quote for puts. Lots are only known per strike
+    // for puts, so see lots_available on each strike.
+    pub options_available: Amount,
+    pub options_available_lots: Option<u64>,
+    pub subscription_period_end: u64,
+    pub option_expiration: u64,
+    pub now: u64,
+    // Negative once passed.
+    pub seconds_to_subscription_end: i64,
+    pub seconds_to_expiration: i64,
+    pub expired: bool,
+    pub strikes: Vec<StrikeReport>,
+    // False when the State lists the maximum number of strikes and the
+    // StrikeStates could not be listed to find the rest. Collateral then
+    // leaves out any strikes past those, so required is too low.
+    pub strikes_complete: bool,
+    pub collateral: CollateralReport,
+    pub pdas: PdaReport,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StrikeReport {
+    // Quote atoms per lot, as stored on chain.
+    pub strike: u64,
+    pub price: f64,
+    pub option_mint: String,
+    // None when the mint does not exist.
+    pub outstanding_lots: Option<u64>,
+    // Collateral that backs the outstanding options.
+    pub outstanding_collateral: Option<Amount>,
+    // Lots that could still be issued at this strike.
+    pub lots_available: u64,
+    pub reverse_option_mint: String,
+    // Only for reversible strikes. Reverse options are backed by the quote
+    // vault.
+    pub reverse_outstanding_lots: Option<u64>,
+}
+
+// Vault balances against what they need to hold. The base vault of a call
+// backs options_available plus every outstanding option. The put vault does
+// the same in quote. The quote vault backs reverse options. Collateral
+// reserved for auctions, shops, grants, merkle claims and stake pools is not
+// minted yet, so it shows up as surplus.
+#[derive(Serialize, Clone, Debug)]
+pub struct CollateralReport {
+    pub vault: String,
+    pub vault_balance: Option<Amount>,
+    pub required: Amount,
+    // Balance minus required. Negative means the vault is short.
+    pub surplus_atoms: Option<i64>,
+    pub quote_vault: String,
+    pub quote_vault_balance: Option<Amount>,
+    pub quote_vault_required: Amount,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PdaReport {
+    // Derived from so_name and base_mint. Differs from the inspected address
+    // only if that is not a real State.
+    pub state: String,
+    pub base_vault: String,
+    pub quote_vault: String,
+    pub put_vault: String,
+    pub fee_schedule: String,
+    pub event_authority: String,
+    pub fee_base_account: String,
+    pub fee_quote_account: String,
+}
+
+// Accounts besides the State that go into the report. Missing accounts are
+// None, for example the quote vault of an SO that is not reversible. Option
+// mints are in the same order as strikes.
+pub struct InspectAccounts {
+    pub strikes: Vec<u64>,
+    pub strikes_complete: bool,
+    pub quote_mint: Mint,
+    pub base_vault: Option<TokenAccount>,
+    pub quote_vault: Option<TokenAccount>,
+    pub put_vault: Option<TokenAccount>,
+    pub option_mints: Vec<Option<Mint>>,
+    pub reverse_option_mints: Vec<Option<Mint>>,
+}
+
+pub fn inspect_state(
+    fetcher: &impl AccountFetcher,
+    address: &Pubkey,
+    now: u64,
+) -> Result<StateReport, ClientError> {
+    let state: State = fetch_state(fetcher, address)?;
+    // State only lists the first MAX_MONITORED_STRIKES. Strikes from before
+    // StrikeStates existed are only on the State, so both are used.
+    let mut strikes: Vec<u64> = state.strikes.clone();
+    let strikes_complete: bool = match fetch_strike_states(fetcher, address)? {
+        Some(mut strike_states) => {
+            strike_states.sort_by_key(|strike_state| strike_state.strike);
+            for strike_state in strike_states {
+                if !strikes.contains(&strike_state.strike) {
+                    strikes.push(strike_state.strike);
+                }
+            }
+            true
+        }
+        None => state.strikes.len() < MAX_MONITORED_STRIKES,
+    };
+    let accounts: InspectAccounts = InspectAccounts {
+        quote_mint: fetch_account(fetcher, &state.quote_mint)?,
+        base_vault: fetch_optional(fetcher, &pda::base_vault(&state.so_name, &state.base_mint))?,
+        quote_vault: fetch_optional(fetcher, &pda::quote_vault(&state.so_name, &state.base_mint))?,
+        put_vault: fetch_optional(fetcher, &pda::put_vault(&state.so_name, &state.base_mint))?,
+        option_mints: strikes
+            .iter()
+            .map(|strike| fetch_optional(fetcher, &pda::option_mint(address, *strike)))
+            .collect::<Result<_, _>>()?,
+        reverse_option_mints: strikes
+            .iter()
+            .map(|strike| fetch_optional(fetcher, &pda::reverse_option_mint(address, *strike)))
+            .collect::<Result<_, _>>()?,
+        strikes,
+        strikes_complete,
+    };
+    Ok(decode_state(address, &state, &accounts, now))
+}
+
+fn fetch_optional<T: anchor_lang::AccountDeserialize>(
+    fetcher: &impl AccountFetcher,
+    address: &Pubkey,
+) -> Result<Option<T>, ClientError> {
+    match fetch_account(fetcher, address) {
+        Ok(account) => Ok(Some(account)),
+        Err(ClientError::AccountNotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Does no fetching, so reports can also be built from saved accounts.
+pub fn decode_state(
+    address: &Pubkey,
+    state: &State,
+    accounts: &InspectAccounts,
+    now: u64,
+) -> StateReport {
+    let quote_decimals: u8 = accounts.quote_mint.decimals;
+    // Units of options_available and the vault that backs the options.
+    let (collateral_decimals, vault, vault_account) = match state.option_type {
+        OptionType::Call => (
+            state.base_decimals,
+            pda::base_vault(&state.so_name, &state.base_mint),
+            &accounts.base_vault,
+        ),
+        OptionType::Put => (
+            quote_decimals,
+            pda::put_vault(&state.so_name, &state.base_mint),
+            &accounts.put_vault,
+        ),
+    };
+
+    let strikes: Vec<StrikeReport> = accounts
+        .strikes
+        .iter()
+        .enumerate()
+        .map(|(i, strike)| {
+            let outstanding_lots: Option<u64> = accounts
+                .option_mints
+                .get(i)
+                .and_then(|mint| mint.as_ref())
+                .map(|mint| mint.supply);
+            StrikeReport {
+                strike: *strike,
+                price: strike_to_price(
+                    *strike,
+                    state.lot_size,
+                    state.base_decimals,
+                    quote_decimals,
+                ),
+                option_mint: pda::option_mint(address, *strike).to_string(),
+                outstanding_lots,
+                outstanding_collateral: outstanding_lots.map(|lots| {
+                    Amount::new(
+                        collateral_for_lots(state, lots, *strike),
+                        collateral_decimals,
+                    )
+                }),
+                lots_available: lots_for_collateral(state, state.options_available, *strike),
+                reverse_option_mint: pda::reverse_option_mint(address, *strike).to_string(),
+                reverse_outstanding_lots: accounts
+                    .reverse_option_mints
+                    .get(i)
+                    .and_then(|mint| mint.as_ref())
+                    .map(|mint| mint.supply),
+            }
+        })
+        .collect();
+
+    let required: u128 = strikes
+        .iter()
+        .filter_map(|strike| strike.outstanding_collateral.as_ref())
+        .map(|collateral| collateral.atoms as u128)
+        .sum::<u128>()
+        + state.options_available as u128;
+    let quote_vault_required: u128 = strikes
+        .iter()
+        .filter_map(|strike| {
+            strike
+                .reverse_outstanding_lots
+                .map(|lots| lots as u128 * strike.strike as u128)
+        })
+        .sum();
+
+    StateReport {
+        address: address.to_string(),
+        so_name: state.so_name.clone(),
+        option_type: format!("{:?}", state.option_type),
+        authority: state.authority.to_string(),
+        issue_authority: state.issue_authority.to_string(),
+        base_mint: state.base_mint.to_string(),
+        quote_mint: state.quote_mint.to_string(),
+        base_decimals: state.base_decimals,
+        quote_decimals,
+        lot_size: Amount::new(state.lot_size, state.base_decimals),
+        options_available: Amount::new(state.options_available, collateral_decimals),
+        options_available_lots: match state.option_type {
+            OptionType::Call => state.options_available.checked_div(state.lot_size),
+            OptionType::Put => None,
+        },
+        subscription_period_end: state.subscription_period_end,
+        option_expiration: state.option_expiration,
+        now,
+        seconds_to_subscription_end: state.subscription_period_end as i64 - now as i64,
+        seconds_to_expiration: state.option_expiration as i64 - now as i64,
+        expired: now > state.option_expiration,
+        strikes,
+        strikes_complete: accounts.strikes_complete,
+        collateral: CollateralReport {
+            vault: vault.to_string(),
+            vault_balance: vault_account
+                .as_ref()
+                .map(|account| Amount::new(account.amount, collateral_decimals)),
+            required: Amount::new(saturate_u64(required), collateral_decimals),
+            surplus_atoms: vault_account
+                .as_ref()
+                .map(|account| saturate_i64(account.amount as i128 - required as i128)),
+            quote_vault: pda::quote_vault(&state.so_name, &state.base_mint).to_string(),
+            quote_vault_balance: accounts
+                .quote_vault
+                .as_ref()
+                .map(|account| Amount::new(account.amount, quote_decimals)),
+            quote_vault_required: Amount::new(saturate_u64(quote_vault_required), quote_decimals),
+        },
+        pdas: PdaReport {
+            state: pda::state(&state.so_name, &state.base_mint).to_string(),
+            base_vault: pda::base_vault(&state.so_name, &state.base_mint).to_string(),
+            quote_vault: pda::quote_vault(&state.so_name, &state.base_mint).to_string(),
+            put_vault: pda::put_vault(&state.so_name, &state.base_mint).to_string(),
+            fee_schedule: pda::fee_schedule().to_string(),
+            event_authority: pda::event_authority().to_string(),
+            fee_base_account: pda::fee_account(&state.base_mint).to_string(),
+            fee_quote_account: pda::fee_account(&state.quote_mint).to_string(),
+        },
+    }
+}
+
+// Same as State::collateral_for_lots, without panicking on overflow since
+// reports are read from accounts that may be corrupted.
+fn collateral_for_lots(state: &State, lots: u64, strike: u64) -> u64 {
+    match state.option_type {
+        OptionType::Call => lots.saturating_mul(state.lot_size),
+        OptionType::Put => lots.saturating_mul(strike),
+    }
+}
+
+fn lots_for_collateral(state: &State, collateral: u64, strike: u64) -> u64 {
+    match state.option_type {
+        OptionType::Call => collateral.checked_div(state.lot_size),
+        OptionType::Put => collateral.checked_div(strike),
+    }
+    .unwrap_or(0)
+}
+
+fn saturate_u64(value: u128) -> u64 {
+    value.min(u64::MAX as u128) as u64
+}
+
+fn saturate_i64(value: i128) -> i64 {
+    value.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountDeserialize, AccountSerialize};
+    use anchor_spl::token::spl_token;
+    use spl_token::solana_program::program_option::COption;
+    use spl_token::solana_program::program_pack::Pack;
+    use staking_options::StrikeState;
+    use std::collections::HashMap;
+
+    const NOW: u64 = 1_000;
+
+    // A call SO with 9 decimal base in lots of one token, and 10 tokens left
+    // to issue.
+    fn call_state() -> State {
+        let mut state: State =
+            State::try_deserialize_unchecked(&mut &vec![0u8; State::SPACE][..]).unwrap();
+        state.so_name = "TEST".to_string();
+        state.base_mint = Pubkey::new_unique();
+        state.quote_mint = Pubkey::new_unique();
+        state.lot_size = 1_000_000_000;
+        state.base_decimals = 9;
+        state.options_available = 10_000_000_000;
+        state.option_expiration = NOW + 100;
+        state.strikes = vec![1_000_000, 2_000_000];
+        state
+    }
+
+    fn mint_data(decimals: u8, supply: u64) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    fn mint(decimals: u8, supply: u64) -> Mint {
+        Mint::try_deserialize(&mut &mint_data(decimals, supply)[..]).unwrap()
+    }
+
+    fn token_account(amount: u64) -> TokenAccount {
+        let mut data: Vec<u8> = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        TokenAccount::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    fn accounts(state: &State) -> InspectAccounts {
+        InspectAccounts {
+            strikes: state.strikes.clone(),
+            strikes_complete: true,
+            quote_mint: mint(6, 0),
+            base_vault: None,
+            quote_vault: None,
+            put_vault: None,
+            option_mints: vec![None; state.strikes.len()],
+            reverse_option_mints: vec![None; state.strikes.len()],
+        }
+    }
+
+    fn serialize<T: AccountSerialize>(account: &T) -> Vec<u8> {
+        let mut data: Vec<u8> = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn decode_call() {
+        let address: Pubkey = Pubkey::new_unique();
+        let state: State = call_state();
+        let mut accounts: InspectAccounts = accounts(&state);
+        accounts.option_mints[0] = Some(mint(0, 3));
+        accounts.base_vault = Some(token_account(12_000_000_000));
+
+        let report: StateReport = decode_state(&address, &state, &accounts, NOW);
+        assert_eq!(report.options_available_lots, Some(10));
+        assert_eq!(report.seconds_to_expiration, 100);
+        assert!(!report.expired);
+
+        assert_eq!(report.strikes[0].price, 1.0);
+        assert_eq!(report.strikes[0].outstanding_lots, Some(3));
+        assert_eq!(
+            report.strikes[0]
+                .outstanding_collateral
+                .as_ref()
+                .unwrap()
+                .atoms,
+            3_000_000_000
+        );
+        assert_eq!(report.strikes[0].lots_available, 10);
+        assert_eq!(report.strikes[1].price, 2.0);
+        assert_eq!(report.strikes[1].outstanding_lots, None);
+
+        // Options available plus the 3 outstanding lots, against 12 tokens.
+        assert_eq!(report.collateral.required.atoms, 13_000_000_000);
+        assert_eq!(report.collateral.required.tokens, 13.0);
+        assert_eq!(report.collateral.surplus_atoms, Some(-1_000_000_000));
+        assert_eq!(
+            report.collateral.vault,
+            pda::base_vault(&state.so_name, &state.base_mint).to_string()
+        );
+    }
+
+    #[test]
+    fn decode_put() {
+        let address: Pubkey = Pubkey::new_unique();
+        let mut state: State = call_state();
+        state.option_type = OptionType::Put;
+        state.options_available = 5_000_000;
+        let mut accounts: InspectAccounts = accounts(&state);
+        accounts.option_mints[1] = Some(mint(0, 2));
+        accounts.reverse_option_mints[0] = Some(mint(0, 1));
+
+        let report: StateReport = decode_state(&address, &state, &accounts, NOW);
+        assert_eq!(report.options_available_lots, None);
+        assert_eq!(report.options_available.tokens, 5.0);
+        assert_eq!(report.strikes[0].lots_available, 5);
+        assert_eq!(report.strikes[1].lots_available, 2);
+
+        // Put collateral is in quote, strike atoms per lot.
+        assert_eq!(report.collateral.required.atoms, 9_000_000);
+        assert_eq!(report.collateral.vault_balance.map(|b| b.atoms), None);
+        assert_eq!(report.collateral.surplus_atoms, None);
+        assert_eq!(report.collateral.quote_vault_required.atoms, 1_000_000);
+    }
+
+    // State with the maximum number of strikes, and one more strike that
+    // only has a StrikeState.
+    fn full_state_fetcher(address: &Pubkey) -> HashMap<Pubkey, Vec<u8>> {
+        let mut state: State = call_state();
+        state.strikes = (1..=MAX_MONITORED_STRIKES as u64).collect();
+        let extra_strike: u64 = 1_000_000;
+
+        let mut strike_state: StrikeState =
+            StrikeState::try_deserialize_unchecked(&mut &vec![0u8; 1_000][..]).unwrap();
+        strike_state.state = *address;
+        strike_state.strike = extra_strike;
+        // Belongs to a different SO, so is not part of the report.
+        let mut other_strike_state: StrikeState = strike_state.clone();
+        other_strike_state.state = Pubkey::new_unique();
+        other_strike_state.strike = 2_000_000;
+
+        HashMap::from([
+            (*address, serialize(&state)),
+            (state.quote_mint, mint_data(6, 0)),
+            (
+                pda::strike_state(address, extra_strike),
+                serialize(&strike_state),
+            ),
+            (
+                pda::strike_state(&other_strike_state.state, 2_000_000),
+                serialize(&other_strike_state),
+            ),
+            (pda::option_mint(address, extra_strike), mint_data(0, 4)),
+        ])
+    }
+
+    #[test]
+    fn inspect_finds_strikes_past_state() {
+        let address: Pubkey = Pubkey::new_unique();
+        let fetcher: HashMap<Pubkey, Vec<u8>> = full_state_fetcher(&address);
+
+        let report: StateReport = inspect_state(&fetcher, &address, NOW).unwrap();
+        assert!(report.strikes_complete);
+        assert_eq!(report.strikes.len(), MAX_MONITORED_STRIKES + 1);
+        let extra: &StrikeReport = report.strikes.last().unwrap();
+        assert_eq!(extra.strike, 1_000_000);
+        assert_eq!(extra.outstanding_lots, Some(4));
+        assert_eq!(report.collateral.required.atoms, 14_000_000_000);
+    }
+
+    #[test]
+    fn inspect_flags_incomplete_strikes() {
+        let address: Pubkey = Pubkey::new_unique();
+        let accounts: HashMap<Pubkey, Vec<u8>> = full_state_fetcher(&address);
+        // Closures can only fetch by address.
+        let fetcher = |address: &Pubkey| accounts.fetch_account_data(address);
+
+        let report: StateReport = inspect_state(&fetcher, &address, NOW).unwrap();
+        assert!(!report.strikes_complete);
+        assert_eq!(report.strikes.len(), MAX_MONITORED_STRIKES);
+        assert_eq!(report.collateral.required.atoms, 10_000_000_000);
+    }
+}
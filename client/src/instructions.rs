@@ -0,0 +1,1367 @@
+use anchor_lang::prelude::{Pubkey, Rent};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::{system_program, sysvar::SysvarId};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use staking_options::accounts as accs;
+use staking_options::instruction as ix;
+use staking_options::{
+    AuctionParams, BarrierKind, ConfigParams, DealType, ExerciseWindow, FeeScheduleParams,
+    OptionType, State, StrikeBreakpoint, StrikeScheduleKind,
+};
+
+use crate::pda;
+
+// Builders for every instruction in the program. Instructions that create or
+// migrate an SO or touch the fee schedule and swap whitelist are free
+// functions. The rest are methods on So, which fills in the accounts that
+// follow from the State.
+//
+// Token accounts of the signer default to their associated token accounts.
+// The event accounts are always passed so events go through the self CPI.
+
+pub fn build_instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: staking_options::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn token_program() -> Pubkey {
+    anchor_spl::token::ID
+}
+
+fn rent() -> Pubkey {
+    Rent::id()
+}
+
+fn event_authority() -> Option<Pubkey> {
+    Some(pda::event_authority())
+}
+
+fn program() -> Option<Pubkey> {
+    Some(staking_options::ID)
+}
+
+// Accounts shared by every version of config. base_account funds the vault
+// and quote_account receives payments on exercise.
+pub struct ConfigAccounts {
+    pub authority: Pubkey,
+    pub so_authority: Pubkey,
+    pub issue_authority: Option<Pubkey>,
+    pub base_account: Pubkey,
+    pub quote_account: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+}
+
+pub fn config(
+    accounts: &ConfigAccounts,
+    option_expiration: u64,
+    subscription_period_end: u64,
+    num_tokens: u64,
+    lot_size: u64,
+    so_name: String,
+) -> Instruction {
+    build_instruction(
+        accs::Config {
+            authority: accounts.authority,
+            so_authority: accounts.so_authority,
+            state: pda::state(&so_name, &accounts.base_mint),
+            base_vault: pda::base_vault(&so_name, &accounts.base_mint),
+            base_account: accounts.base_account,
+            quote_account: accounts.quote_account,
+            base_mint: accounts.base_mint,
+            quote_mint: accounts.quote_mint,
+            token_program: token_program(),
+            system_program: system_program::ID,
+            rent: rent(),
+            event_authority: event_authority(),
+            program: program(),
+        },
+        ix::Config {
+            option_expiration,
+            subscription_period_end,
+            num_tokens,
+            lot_size,
+            so_name,
+        },
+    )
+}
+
+pub fn config_v2(
+    accounts: &ConfigAccounts,
+    option_expiration: u64,
+    subscription_period_end: u64,
+    num_tokens: u64,
+    lot_size: u64,
+    so_name: String,
+) -> Instruction {
+    build_instruction(
+        accs::ConfigV2 {
+            authority: accounts.authority,
+            so_authority: accounts.so_authority,
+            issue_authority: accounts.issue_authority,
+            state: pda::state(&so_name, &accounts.base_mint),
+            base_vault: pda::base_vault(&so_name, &accounts.base_mint),
+            base_account: accounts.base_account,
+            quote_account: accounts.quote_account,
+            base_mint: accounts.base_mint,
+            quote_mint: accounts.quote_mint,
+            token_program: token_program(),
+            system_program: system_program::ID,
+            rent: rent(),
+            event_authority: event_authority(),
+            program: program(),
+        },
+        ix::ConfigV2 {
+            option_expiration,
+            subscription_period_end,
+            num_tokens,
+            lot_size,
+            so_name,
+        },
+    )
+}
+
+pub fn config_v3(
+    accounts: &ConfigAccounts,
+    option_expiration: u64,
+    subscription_period_end: u64,
+    num_tokens: u64,
+    lot_size: u64,
+    so_name: String,
+) -> Instruction {
+    build_instruction(
+        accs::ConfigV3 {
+            authority: accounts.authority,
+            so_authority: accounts.so_authority,
+            issue_authority: accounts.issue_authority,
+            state: pda::state(&so_name, &accounts.base_mint),
+            base_vault: pda::base_vault(&so_name, &accounts.base_mint),
+            quote_vault: pda::quote_vault(&so_name, &accounts.base_mint),
+            base_account: accounts.base_account,
+            quote_account: accounts.quote_account,
+            base_mint: accounts.base_mint,
+            quote_mint: accounts.quote_mint,
+            token_program: token_program(),
+            system_program: system_program::ID,
+            rent: rent(),
+            event_authority: event_authority(),
+            program: program(),
+        },
+        ix::ConfigV3 {
+            option_expiration,
+            subscription_period_end,
+            num_tokens,
+            lot_size,
+            so_name,
+        },
+    )
+}
+
+// For puts the quote_account funds the put vault and the base_account
+// receives the base delivered on exercise.
+pub fn config_v4(accounts: &ConfigAccounts, params: ConfigParams) -> Instruction {
+    let so_name: &str = &params.so_name;
+    let put: bool = params.option_type == OptionType::Put;
+    build_instruction(
+        accs::ConfigV4 {
+            authority: accounts.authority,
+            so_authority: accounts.so_authority,
+            issue_authority: accounts.issue_authority,
+            state: pda::state(so_name, &accounts.base_mint),
+            base_vault: if put {
+                None
+            } else {
+                Some(pda::base_vault(so_name, &accounts.base_mint))
+            },
+            quote_vault: if params.reversible {
+                Some(pda::quote_vault(so_name, &accounts.base_mint))
+            } else {
+                None
+            },
+            put_vault: if put {
+                Some(pda::put_vault(so_name, &accounts.base_mint))
+            } else {
+                None
+            },
+            base_account: accounts.base_account,
+            quote_account: accounts.quote_account,
+            base_mint: accounts.base_mint,
+            quote_mint: accounts.quote_mint,
+            token_program: token_program(),
+            system_program: system_program::ID,
+            rent: rent(),
+            event_authority: event_authority(),
+            program: program(),
+        },
+        ix::ConfigV4 { params },
+    )
+}
+
+// Older States may not deserialize with the current layout, so this takes
+// what the seeds need instead of a So.
+pub fn migrate_state(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    so_name: String,
+    base_mint: &Pubkey,
+    quote_mint: &Pubkey,
+) -> Instruction {
+    build_instruction(
+        accs::MigrateState {
+            authority: *authority,
+            payer: *payer,
+            state: pda::state(&so_name, base_mint),
+            quote_vault: pda::quote_vault(&so_name, base_mint),
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            token_program: token_program(),
+            system_program: system_program::ID,
+            rent: rent(),
+        },
+        ix::MigrateState { so_name },
+    )
+}
+
+pub fn init_fee_schedule(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    params: FeeScheduleParams,
+) -> Instruction {
+    build_instruction(
+        accs::InitFeeSchedule {
+            authority: *authority,
+            payer: *payer,
+            fee_schedule: pda::fee_schedule(),
+            system_program: system_program::ID,
+        },
+        ix::InitFeeSchedule { params },
+    )
+}
+
+pub fn update_fee_schedule(authority: &Pubkey, params: FeeScheduleParams) -> Instruction {
+    build_instruction(
+        accs::UpdateFeeSchedule {
+            authority: *authority,
+            fee_schedule: pda::fee_schedule(),
+        },
+        ix::UpdateFeeSchedule { params },
+    )
+}
+
+pub fn read_fee_bps(base_mint: &Pubkey, quote_mint: &Pubkey, deal_type: DealType) -> Instruction {
+    build_instruction(
+        accs::ReadFeeBps {
+            fee_schedule: pda::fee_schedule(),
+        },
+        ix::ReadFeeBps {
+            base_mint: *base_mint,
+            quote_mint: *quote_mint,
+            deal_type,
+        },
+    )
+}
+
+pub fn add_swap_program(authority: &Pubkey, payer: &Pubkey, swap_program: &Pubkey) -> Instruction {
+    build_instruction(
+        accs::AddSwapProgram {
+            authority: *authority,
+            payer: *payer,
+            swap_whitelist_entry: pda::swap_whitelist_entry(swap_program),
+            system_program: system_program::ID,
+        },
+        ix::AddSwapProgram {
+            program: *swap_program,
+        },
+    )
+}
+
+pub fn remove_swap_program(authority: &Pubkey, swap_program: &Pubkey) -> Instruction {
+    build_instruction(
+        accs::RemoveSwapProgram {
+            authority: *authority,
+            swap_whitelist_entry: pda::swap_whitelist_entry(swap_program),
+        },
+        ix::RemoveSwapProgram {},
+    )
+}
+
+// An existing SO. The State only needs to be fetched once, since the fields
+// used here do not change after config.
+pub struct So {
+    pub address: Pubkey,
+    pub state: State,
+}
+
+impl So {
+    pub fn new(address: Pubkey, state: State) -> Self {
+        So { address, state }
+    }
+
+    pub fn base_vault(&self) -> Pubkey {
+        pda::base_vault(&self.state.so_name, &self.state.base_mint)
+    }
+
+    pub fn quote_vault(&self) -> Pubkey {
+        pda::quote_vault(&self.state.so_name, &self.state.base_mint)
+    }
+
+    pub fn put_vault(&self) -> Pubkey {
+        pda::put_vault(&self.state.so_name, &self.state.base_mint)
+    }
+
+    pub fn option_mint(&self, strike: u64) -> Pubkey {
+        pda::option_mint(&self.address, strike)
+    }
+
+    pub fn reverse_option_mint(&self, strike: u64) -> Pubkey {
+        pda::reverse_option_mint(&self.address, strike)
+    }
+
+    pub fn strike_state(&self, strike: u64) -> Pubkey {
+        pda::strike_state(&self.address, strike)
+    }
+
+    pub fn option_account(&self, owner: &Pubkey, strike: u64) -> Pubkey {
+        pda::associated_token(owner, &self.option_mint(strike))
+    }
+
+    pub fn base_account(&self, owner: &Pubkey) -> Pubkey {
+        pda::associated_token(owner, &self.state.base_mint)
+    }
+
+    pub fn quote_account(&self, owner: &Pubkey) -> Pubkey {
+        pda::associated_token(owner, &self.state.quote_mint)
+    }
+
+    pub fn add_tokens(
+        &self,
+        authority: &Pubkey,
+        base_account: &Pubkey,
+        num_tokens_to_add: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::AddTokens {
+                authority: *authority,
+                state: self.address,
+                base_vault: self.base_vault(),
+                base_account: *base_account,
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::AddTokens { num_tokens_to_add },
+        )
+    }
+
+    pub fn add_tokens_put(
+        &self,
+        authority: &Pubkey,
+        quote_account: &Pubkey,
+        num_tokens_to_add: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::AddTokensPut {
+                authority: *authority,
+                state: self.address,
+                put_vault: self.put_vault(),
+                quote_account: *quote_account,
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::AddTokensPut { num_tokens_to_add },
+        )
+    }
+
+    pub fn init_strike(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::InitStrike {
+                authority: *authority,
+                state: self.address,
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                token_program: token_program(),
+                system_program: system_program::ID,
+                rent: rent(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::InitStrike { strike },
+        )
+    }
+
+    pub fn init_strike_with_payer(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        strike: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::InitStrikeWithPayer {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                token_program: token_program(),
+                system_program: system_program::ID,
+                rent: rent(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::InitStrikeWithPayer { strike },
+        )
+    }
+
+    pub fn init_strike_reversible(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        strike: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::InitStrikeReversible {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                reverse_option_mint: self.reverse_option_mint(strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                token_program: token_program(),
+                system_program: system_program::ID,
+                rent: rent(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::InitStrikeReversible { strike },
+        )
+    }
+
+    // For strikes created before StrikeState existed.
+    pub fn init_strike_state(&self, payer: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::InitStrikeState {
+                payer: *payer,
+                state: self.address,
+                option_mint: self.option_mint(strike),
+                reverse_option_mint: self.reverse_option_mint(strike),
+                strike_state: self.strike_state(strike),
+                system_program: system_program::ID,
+            },
+            ix::InitStrikeState { strike },
+        )
+    }
+
+    // recipient_so_account is an option token account, not a wallet.
+    pub fn issue(
+        &self,
+        authority: &Pubkey,
+        recipient_so_account: &Pubkey,
+        amount: u64,
+        strike: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::Issue {
+                authority: *authority,
+                state: self.address,
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                user_so_account: *recipient_so_account,
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::Issue { amount, strike },
+        )
+    }
+
+    fn exercise_accounts(&self, authority: &Pubkey, strike: u64) -> accs::Exercise {
+        accs::Exercise {
+            authority: *authority,
+            state: self.address,
+            user_so_account: self.option_account(authority, strike),
+            option_mint: self.option_mint(strike),
+            strike_state: self.strike_state(strike),
+            user_quote_account: self.quote_account(authority),
+            project_quote_account: self.state.quote_account,
+            fee_quote_account: pda::fee_account(&self.state.quote_mint),
+            fee_schedule: pda::fee_schedule(),
+            base_vault: self.base_vault(),
+            user_base_account: self.base_account(authority),
+            token_program: token_program(),
+            event_authority: event_authority(),
+            program: program(),
+        }
+    }
+
+    pub fn exercise(&self, authority: &Pubkey, amount: u64, strike: u64) -> Instruction {
+        build_instruction(
+            self.exercise_accounts(authority, strike),
+            ix::Exercise { amount, strike },
+        )
+    }
+
+    pub fn exercise_reversible(&self, authority: &Pubkey, amount: u64, strike: u64) -> Instruction {
+        build_instruction(
+            accs::ExerciseReversible {
+                authority: *authority,
+                state: self.address,
+                user_so_account: self.option_account(authority, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                user_reverse_so_account: pda::associated_token(
+                    authority,
+                    &self.reverse_option_mint(strike),
+                ),
+                reverse_option_mint: self.reverse_option_mint(strike),
+                user_quote_account: self.quote_account(authority),
+                quote_vault: self.quote_vault(),
+                base_vault: self.base_vault(),
+                user_base_account: self.base_account(authority),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ExerciseReversible { amount, strike },
+        )
+    }
+
+    pub fn reverse_exercise(&self, authority: &Pubkey, amount: u64, strike: u64) -> Instruction {
+        build_instruction(
+            accs::ReverseExercise {
+                authority: *authority,
+                state: self.address,
+                user_so_account: self.option_account(authority, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                user_reverse_so_account: pda::associated_token(
+                    authority,
+                    &self.reverse_option_mint(strike),
+                ),
+                reverse_option_mint: self.reverse_option_mint(strike),
+                user_quote_account: self.quote_account(authority),
+                quote_vault: self.quote_vault(),
+                base_vault: self.base_vault(),
+                user_base_account: self.base_account(authority),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ReverseExercise { amount, strike },
+        )
+    }
+
+    // For puts, state.quote_account is the project account receiving base.
+    pub fn exercise_put(&self, authority: &Pubkey, amount: u64, strike: u64) -> Instruction {
+        build_instruction(
+            accs::ExercisePut {
+                authority: *authority,
+                state: self.address,
+                user_so_account: self.option_account(authority, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                user_base_account: self.base_account(authority),
+                project_base_account: self.state.quote_account,
+                put_vault: self.put_vault(),
+                user_quote_account: self.quote_account(authority),
+                fee_quote_account: pda::fee_account(&self.state.quote_mint),
+                fee_schedule: pda::fee_schedule(),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ExercisePut { amount, strike },
+        )
+    }
+
+    pub fn exercise_cash(&self, authority: &Pubkey, amount: u64, strike: u64) -> Instruction {
+        build_instruction(
+            accs::ExerciseCash {
+                authority: *authority,
+                state: self.address,
+                user_so_account: self.option_account(authority, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                oracle: self.state.oracle,
+                quote_mint: self.state.quote_mint,
+                base_vault: self.base_vault(),
+                user_base_account: self.base_account(authority),
+                fee_base_account: pda::fee_account(&self.state.base_mint),
+                fee_schedule: pda::fee_schedule(),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ExerciseCash { amount, strike },
+        )
+    }
+
+    // The project receives the strike in base, so project_base_account is a
+    // base token account of the project.
+    pub fn net_exercise(
+        &self,
+        authority: &Pubkey,
+        project_base_account: &Pubkey,
+        amount: u64,
+        strike: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::NetExercise {
+                authority: *authority,
+                state: self.address,
+                user_so_account: self.option_account(authority, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                oracle: self.state.oracle,
+                quote_mint: self.state.quote_mint,
+                base_vault: self.base_vault(),
+                user_base_account: self.base_account(authority),
+                project_base_account: *project_base_account,
+                fee_base_account: pda::fee_account(&self.state.base_mint),
+                fee_schedule: pda::fee_schedule(),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::NetExercise { amount, strike },
+        )
+    }
+
+    // swap_accounts are passed through to the swap program in order.
+    pub fn flash_exercise(
+        &self,
+        authority: &Pubkey,
+        swap_program: &Pubkey,
+        swap_accounts: Vec<AccountMeta>,
+        amount: u64,
+        strike: u64,
+        swap_data: Vec<u8>,
+    ) -> Instruction {
+        let exercise: accs::Exercise = self.exercise_accounts(authority, strike);
+        let mut instruction: Instruction = build_instruction(
+            accs::FlashExercise {
+                authority: exercise.authority,
+                state: exercise.state,
+                user_so_account: exercise.user_so_account,
+                option_mint: exercise.option_mint,
+                strike_state: exercise.strike_state,
+                user_quote_account: exercise.user_quote_account,
+                project_quote_account: exercise.project_quote_account,
+                fee_quote_account: exercise.fee_quote_account,
+                fee_schedule: exercise.fee_schedule,
+                base_vault: exercise.base_vault,
+                user_base_account: exercise.user_base_account,
+                swap_program: *swap_program,
+                swap_whitelist_entry: pda::swap_whitelist_entry(swap_program),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::FlashExercise {
+                amount,
+                strike,
+                swap_data,
+            },
+        );
+        instruction.accounts.extend(swap_accounts);
+        instruction
+    }
+
+    pub fn register_auto_exercise(
+        &self,
+        authority: &Pubkey,
+        strike: u64,
+        max_lots: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::RegisterAutoExercise {
+                authority: *authority,
+                state: self.address,
+                auto_exercise: pda::auto_exercise(&self.address, strike, authority),
+                strike_state: self.strike_state(strike),
+                user_so_account: self.option_account(authority, strike),
+                user_quote_account: self.quote_account(authority),
+                user_base_account: self.base_account(authority),
+                token_program: token_program(),
+                system_program: system_program::ID,
+            },
+            ix::RegisterAutoExercise { strike, max_lots },
+        )
+    }
+
+    pub fn unregister_auto_exercise(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::UnregisterAutoExercise {
+                authority: *authority,
+                auto_exercise: pda::auto_exercise(&self.address, strike, authority),
+                user_so_account: self.option_account(authority, strike),
+                user_quote_account: self.quote_account(authority),
+                token_program: token_program(),
+            },
+            ix::UnregisterAutoExercise { strike },
+        )
+    }
+
+    // Anyone can crank. Assumes the owner registered their associated token
+    // accounts, which is what register_auto_exercise above does.
+    pub fn crank_auto_exercise(&self, owner: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::CrankAutoExercise {
+                state: self.address,
+                auto_exercise: pda::auto_exercise(&self.address, strike, owner),
+                user_so_account: self.option_account(owner, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                oracle: self.state.oracle,
+                quote_mint: self.state.quote_mint,
+                user_quote_account: self.quote_account(owner),
+                project_quote_account: self.state.quote_account,
+                fee_quote_account: pda::fee_account(&self.state.quote_mint),
+                fee_schedule: pda::fee_schedule(),
+                base_vault: self.base_vault(),
+                user_base_account: self.base_account(owner),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::CrankAutoExercise { strike },
+        )
+    }
+
+    pub fn quote_exercise(&self, amount_lots: u64, strike: u64, exerciser: &Pubkey) -> Instruction {
+        build_instruction(
+            accs::QuoteExercise {
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                fee_schedule: pda::fee_schedule(),
+            },
+            ix::QuoteExercise {
+                amount_lots,
+                strike,
+                exerciser: *exerciser,
+            },
+        )
+    }
+
+    pub fn quote_reverse_exercise(&self, amount_lots: u64, strike: u64) -> Instruction {
+        build_instruction(
+            accs::QuoteReverseExercise {
+                state: self.address,
+                strike_state: self.strike_state(strike),
+            },
+            ix::QuoteReverseExercise {
+                amount_lots,
+                strike,
+            },
+        )
+    }
+
+    pub fn quote_withdraw_all(&self, quote_account: &Pubkey) -> Instruction {
+        build_instruction(
+            accs::QuoteWithdrawAll {
+                state: self.address,
+                base_vault: self.base_vault(),
+                quote_vault: self.quote_vault(),
+                quote_account: *quote_account,
+                fee_schedule: pda::fee_schedule(),
+            },
+            ix::QuoteWithdrawAll {},
+        )
+    }
+
+    pub fn create_auction(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        strike: u64,
+        params: AuctionParams,
+    ) -> Instruction {
+        build_instruction(
+            accs::CreateAuction {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                auction: pda::auction(&self.address, strike),
+                system_program: system_program::ID,
+            },
+            ix::CreateAuction { strike, params },
+        )
+    }
+
+    pub fn buy_from_auction(
+        &self,
+        authority: &Pubkey,
+        strike: u64,
+        amount_lots: u64,
+        max_price_per_lot: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::BuyFromAuction {
+                authority: *authority,
+                state: self.address,
+                auction: pda::auction(&self.address, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                buyer_so_account: self.option_account(authority, strike),
+                buyer_quote_account: self.quote_account(authority),
+                project_quote_account: self.state.quote_account,
+                fee_quote_account: pda::fee_account(&self.state.quote_mint),
+                fee_schedule: pda::fee_schedule(),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::BuyFromAuction {
+                strike,
+                amount_lots,
+                max_price_per_lot,
+            },
+        )
+    }
+
+    pub fn close_auction(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::CloseAuction {
+                authority: *authority,
+                state: self.address,
+                auction: pda::auction(&self.address, strike),
+            },
+            ix::CloseAuction { strike },
+        )
+    }
+
+    pub fn create_shop(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        strike: u64,
+        premium_per_lot: u64,
+        max_lots: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::CreateShop {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                shop: pda::shop(&self.address, strike),
+                system_program: system_program::ID,
+            },
+            ix::CreateShop {
+                strike,
+                premium_per_lot,
+                max_lots,
+            },
+        )
+    }
+
+    pub fn buy_from_shop(
+        &self,
+        authority: &Pubkey,
+        strike: u64,
+        amount_lots: u64,
+        max_premium_per_lot: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::BuyFromShop {
+                authority: *authority,
+                state: self.address,
+                shop: pda::shop(&self.address, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                buyer_so_account: self.option_account(authority, strike),
+                buyer_quote_account: self.quote_account(authority),
+                project_quote_account: self.state.quote_account,
+                fee_quote_account: pda::fee_account(&self.state.quote_mint),
+                fee_schedule: pda::fee_schedule(),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::BuyFromShop {
+                strike,
+                amount_lots,
+                max_premium_per_lot,
+            },
+        )
+    }
+
+    pub fn set_shop_premium(
+        &self,
+        authority: &Pubkey,
+        strike: u64,
+        premium_per_lot: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::SetShopPremium {
+                authority: *authority,
+                state: self.address,
+                shop: pda::shop(&self.address, strike),
+            },
+            ix::SetShopPremium {
+                strike,
+                premium_per_lot,
+            },
+        )
+    }
+
+    pub fn close_shop(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::CloseShop {
+                authority: *authority,
+                state: self.address,
+                shop: pda::shop(&self.address, strike),
+            },
+            ix::CloseShop { strike },
+        )
+    }
+
+    pub fn create_stake_pool(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        stake_mint: &Pubkey,
+        strike: u64,
+        total_lots: u64,
+    ) -> Instruction {
+        let stake_pool: Pubkey = pda::stake_pool(&self.address, strike);
+        build_instruction(
+            accs::CreateStakePool {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                stake_pool,
+                stake_mint: *stake_mint,
+                stake_vault: pda::stake_vault(&stake_pool),
+                token_program: token_program(),
+                system_program: system_program::ID,
+                rent: rent(),
+            },
+            ix::CreateStakePool { strike, total_lots },
+        )
+    }
+
+    pub fn stake(
+        &self,
+        authority: &Pubkey,
+        stake_mint: &Pubkey,
+        strike: u64,
+        amount: u64,
+    ) -> Instruction {
+        let stake_pool: Pubkey = pda::stake_pool(&self.address, strike);
+        build_instruction(
+            accs::Stake {
+                authority: *authority,
+                state: self.address,
+                stake_pool,
+                stake_entry: pda::stake_entry(&stake_pool, authority),
+                stake_vault: pda::stake_vault(&stake_pool),
+                user_stake_account: pda::associated_token(authority, stake_mint),
+                token_program: token_program(),
+                system_program: system_program::ID,
+            },
+            ix::Stake { strike, amount },
+        )
+    }
+
+    pub fn unstake(
+        &self,
+        authority: &Pubkey,
+        stake_mint: &Pubkey,
+        strike: u64,
+        amount: u64,
+    ) -> Instruction {
+        let stake_pool: Pubkey = pda::stake_pool(&self.address, strike);
+        build_instruction(
+            accs::Unstake {
+                authority: *authority,
+                stake_pool,
+                stake_entry: pda::stake_entry(&stake_pool, authority),
+                stake_vault: pda::stake_vault(&stake_pool),
+                user_stake_account: pda::associated_token(authority, stake_mint),
+                token_program: token_program(),
+            },
+            ix::Unstake { strike, amount },
+        )
+    }
+
+    pub fn claim_staking_options(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        let stake_pool: Pubkey = pda::stake_pool(&self.address, strike);
+        build_instruction(
+            accs::ClaimStakingOptions {
+                authority: *authority,
+                state: self.address,
+                stake_pool,
+                stake_entry: pda::stake_entry(&stake_pool, authority),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                user_so_account: self.option_account(authority, strike),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ClaimStakingOptions { strike },
+        )
+    }
+
+    pub fn close_stake_pool(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::CloseStakePool {
+                authority: *authority,
+                state: self.address,
+                stake_pool: pda::stake_pool(&self.address, strike),
+            },
+            ix::CloseStakePool { strike },
+        )
+    }
+
+    pub fn set_merkle_root(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        strike: u64,
+        root: [u8; 32],
+        num_leaves: u64,
+        total_lots: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::SetMerkleRoot {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                merkle_distributor: pda::merkle_distributor(&self.address, strike),
+                system_program: system_program::ID,
+            },
+            ix::SetMerkleRoot {
+                strike,
+                root,
+                num_leaves,
+                total_lots,
+            },
+        )
+    }
+
+    pub fn claim(
+        &self,
+        claimant: &Pubkey,
+        strike: u64,
+        index: u64,
+        lots: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Instruction {
+        build_instruction(
+            accs::Claim {
+                claimant: *claimant,
+                state: self.address,
+                merkle_distributor: pda::merkle_distributor(&self.address, strike),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                claimant_so_account: self.option_account(claimant, strike),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::Claim {
+                strike,
+                index,
+                lots,
+                proof,
+            },
+        )
+    }
+
+    pub fn close_merkle_distributor(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::CloseMerkleDistributor {
+                authority: *authority,
+                state: self.address,
+                merkle_distributor: pda::merkle_distributor(&self.address, strike),
+            },
+            ix::CloseMerkleDistributor { strike },
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_grant(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        recipient: &Pubkey,
+        strike: u64,
+        total_lots: u64,
+        start: u64,
+        cliff: u64,
+        end: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::CreateGrant {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                recipient: *recipient,
+                vesting_grant: pda::vesting_grant(&self.address, strike, recipient),
+                system_program: system_program::ID,
+            },
+            ix::CreateGrant {
+                strike,
+                total_lots,
+                start,
+                cliff,
+                end,
+            },
+        )
+    }
+
+    // Signed by the recipient of the grant.
+    pub fn claim_vested(&self, authority: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::ClaimVested {
+                authority: *authority,
+                state: self.address,
+                vesting_grant: pda::vesting_grant(&self.address, strike, authority),
+                option_mint: self.option_mint(strike),
+                strike_state: self.strike_state(strike),
+                user_so_account: self.option_account(authority, strike),
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ClaimVested { strike },
+        )
+    }
+
+    pub fn revoke_grant(&self, authority: &Pubkey, recipient: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::RevokeGrant {
+                authority: *authority,
+                state: self.address,
+                vesting_grant: pda::vesting_grant(&self.address, strike, recipient),
+                strike_state: self.strike_state(strike),
+            },
+            ix::RevokeGrant { strike },
+        )
+    }
+
+    pub fn name_token(&self, authority: &Pubkey, payer: &Pubkey, strike: u64) -> Instruction {
+        let option_mint: Pubkey = self.option_mint(strike);
+        build_instruction(
+            accs::NameToken {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                option_mint,
+                option_mint_metadata_account: pda::option_mint_metadata(&option_mint),
+                token_metadata_program: mpl_token_metadata::ID,
+                system_program: system_program::ID,
+                rent: rent(),
+            },
+            ix::NameToken { strike },
+        )
+    }
+
+    // Signed by the fee schedule authority.
+    pub fn set_deal_type(&self, authority: &Pubkey, deal_type: DealType) -> Instruction {
+        build_instruction(
+            accs::SetDealType {
+                authority: *authority,
+                fee_schedule: pda::fee_schedule(),
+                state: self.address,
+            },
+            ix::SetDealType { deal_type },
+        )
+    }
+
+    pub fn set_exercise_schedule(
+        &self,
+        authority: &Pubkey,
+        exercise_start: u64,
+        exercise_windows: Vec<ExerciseWindow>,
+    ) -> Instruction {
+        build_instruction(
+            accs::SetExerciseSchedule {
+                authority: *authority,
+                state: self.address,
+            },
+            ix::SetExerciseSchedule {
+                exercise_start,
+                exercise_windows,
+            },
+        )
+    }
+
+    pub fn set_net_exercise_haircut(&self, authority: &Pubkey, haircut_bps: u16) -> Instruction {
+        build_instruction(
+            accs::SetNetExerciseHaircut {
+                authority: *authority,
+                state: self.address,
+            },
+            ix::SetNetExerciseHaircut { haircut_bps },
+        )
+    }
+
+    pub fn set_oracle(&self, authority: &Pubkey, oracle: &Pubkey, max_age_sec: u64) -> Instruction {
+        build_instruction(
+            accs::SetOracle {
+                authority: *authority,
+                state: self.address,
+                oracle: *oracle,
+            },
+            ix::SetOracle { max_age_sec },
+        )
+    }
+
+    pub fn set_barrier(
+        &self,
+        authority: &Pubkey,
+        barrier_oracle: &Pubkey,
+        strike: u64,
+        barrier_kind: BarrierKind,
+        barrier_price: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::SetBarrier {
+                authority: *authority,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                barrier_oracle: *barrier_oracle,
+            },
+            ix::SetBarrier {
+                strike,
+                barrier_kind,
+                barrier_price,
+            },
+        )
+    }
+
+    pub fn trigger_barrier(&self, barrier_oracle: &Pubkey, strike: u64) -> Instruction {
+        build_instruction(
+            accs::TriggerBarrier {
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                barrier_oracle: *barrier_oracle,
+                quote_mint: self.state.quote_mint,
+            },
+            ix::TriggerBarrier { strike },
+        )
+    }
+
+    pub fn set_strike_cap(&self, authority: &Pubkey, strike: u64, cap: u64) -> Instruction {
+        build_instruction(
+            accs::SetStrikeCap {
+                authority: *authority,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+            },
+            ix::SetStrikeCap { strike, cap },
+        )
+    }
+
+    pub fn set_strike_schedule(
+        &self,
+        authority: &Pubkey,
+        payer: &Pubkey,
+        strike: u64,
+        schedule_kind: StrikeScheduleKind,
+        breakpoints: Vec<StrikeBreakpoint>,
+    ) -> Instruction {
+        build_instruction(
+            accs::SetStrikeSchedule {
+                authority: *authority,
+                payer: *payer,
+                state: self.address,
+                strike_state: self.strike_state(strike),
+                system_program: system_program::ID,
+            },
+            ix::SetStrikeSchedule {
+                strike,
+                schedule_kind,
+                breakpoints,
+            },
+        )
+    }
+
+    pub fn withdraw(&self, authority: &Pubkey, base_account: &Pubkey) -> Instruction {
+        build_instruction(
+            accs::Withdraw {
+                authority: *authority,
+                state: self.address,
+                base_vault: self.base_vault(),
+                base_account: *base_account,
+                token_program: token_program(),
+                system_program: system_program::ID,
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::Withdraw {},
+        )
+    }
+
+    pub fn withdraw_all(
+        &self,
+        authority: &Pubkey,
+        base_account: &Pubkey,
+        quote_account: &Pubkey,
+    ) -> Instruction {
+        build_instruction(
+            accs::WithdrawAll {
+                authority: *authority,
+                state: self.address,
+                base_vault: self.base_vault(),
+                base_account: *base_account,
+                quote_vault: self.quote_vault(),
+                quote_account: *quote_account,
+                fee_quote_account: pda::fee_account(&self.state.quote_mint),
+                fee_schedule: pda::fee_schedule(),
+                token_program: token_program(),
+                system_program: system_program::ID,
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::WithdrawAll {},
+        )
+    }
+
+    pub fn withdraw_put(&self, authority: &Pubkey, quote_account: &Pubkey) -> Instruction {
+        build_instruction(
+            accs::WithdrawPut {
+                authority: *authority,
+                state: self.address,
+                put_vault: self.put_vault(),
+                quote_account: *quote_account,
+                token_program: token_program(),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::WithdrawPut {},
+        )
+    }
+
+    // The authority has to hold every outstanding option of the strike.
+    pub fn modify_expiration(
+        &self,
+        authority: &Pubkey,
+        strike: u64,
+        new_expiration_unix_sec: u64,
+    ) -> Instruction {
+        build_instruction(
+            accs::ModifyExpiration {
+                authority: *authority,
+                state: self.address,
+                user_so_account: self.option_account(authority, strike),
+                option_mint: self.option_mint(strike),
+                event_authority: event_authority(),
+                program: program(),
+            },
+            ix::ModifyExpiration {
+                new_expiration_unix_sec,
+            },
+        )
+    }
+}
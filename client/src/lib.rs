@@ -0,0 +1,16 @@
+// Client for the staking options program. Builds instructions, derives the
+// addresses the program uses and converts between the units in State.
+//
+// Sending transactions is left to the caller so this works with any RPC
+// client, banks client or test harness.
+
+pub mod fetch;
+pub mod inspect;
+pub mod instructions;
+pub mod pda;
+pub mod units;
+
+pub use crate::fetch::*;
+pub use crate::inspect::*;
+pub use crate::instructions::*;
+pub use staking_options::ID;
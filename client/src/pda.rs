@@ -0,0 +1,174 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address;
+use staking_options::{
+    DUAL_DAO_ADDRESS, EVENT_AUTHORITY_SEED, FEE_SCHEDULE_SEED, SO_AUCTION_SEED,
+    SO_AUTO_EXERCISE_SEED, SO_CONFIG_SEED, SO_GRANT_SEED, SO_MERKLE_SEED, SO_MINT_SEED,
+    SO_PUT_VAULT_SEED, SO_REVERSE_MINT_SEED, SO_REVERSE_VAULT_SEED, SO_SHOP_SEED,
+    SO_STAKE_ENTRY_SEED, SO_STAKE_POOL_SEED, SO_STAKE_VAULT_SEED, SO_STRIKE_SEED, SO_VAULT_SEED,
+    SWAP_WHITELIST_SEED,
+};
+
+// Addresses of every PDA used by the program. The bump is dropped since
+// instructions only need the address.
+
+fn find(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &staking_options::ID).0
+}
+
+pub fn state(so_name: &str, base_mint: &Pubkey) -> Pubkey {
+    find(&[SO_CONFIG_SEED, so_name.as_bytes(), &base_mint.to_bytes()])
+}
+
+pub fn base_vault(so_name: &str, base_mint: &Pubkey) -> Pubkey {
+    find(&[SO_VAULT_SEED, so_name.as_bytes(), &base_mint.to_bytes()])
+}
+
+// Holds quote tokens for reversible strikes.
+pub fn quote_vault(so_name: &str, base_mint: &Pubkey) -> Pubkey {
+    find(&[
+        SO_REVERSE_VAULT_SEED,
+        so_name.as_bytes(),
+        &base_mint.to_bytes(),
+    ])
+}
+
+pub fn put_vault(so_name: &str, base_mint: &Pubkey) -> Pubkey {
+    find(&[SO_PUT_VAULT_SEED, so_name.as_bytes(), &base_mint.to_bytes()])
+}
+
+pub fn option_mint(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[SO_MINT_SEED, &state.to_bytes(), &strike.to_be_bytes()])
+}
+
+pub fn reverse_option_mint(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[
+        SO_REVERSE_MINT_SEED,
+        &state.to_bytes(),
+        &strike.to_be_bytes(),
+    ])
+}
+
+pub fn strike_state(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[SO_STRIKE_SEED, &state.to_bytes(), &strike.to_be_bytes()])
+}
+
+pub fn fee_schedule() -> Pubkey {
+    find(&[FEE_SCHEDULE_SEED])
+}
+
+pub fn vesting_grant(state: &Pubkey, strike: u64, recipient: &Pubkey) -> Pubkey {
+    find(&[
+        SO_GRANT_SEED,
+        &state.to_bytes(),
+        &strike.to_be_bytes(),
+        &recipient.to_bytes(),
+    ])
+}
+
+pub fn auto_exercise(state: &Pubkey, strike: u64, owner: &Pubkey) -> Pubkey {
+    find(&[
+        SO_AUTO_EXERCISE_SEED,
+        &state.to_bytes(),
+        &strike.to_be_bytes(),
+        &owner.to_bytes(),
+    ])
+}
+
+pub fn swap_whitelist_entry(swap_program: &Pubkey) -> Pubkey {
+    find(&[SWAP_WHITELIST_SEED, &swap_program.to_bytes()])
+}
+
+pub fn auction(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[SO_AUCTION_SEED, &state.to_bytes(), &strike.to_be_bytes()])
+}
+
+pub fn shop(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[SO_SHOP_SEED, &state.to_bytes(), &strike.to_be_bytes()])
+}
+
+pub fn merkle_distributor(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[SO_MERKLE_SEED, &state.to_bytes(), &strike.to_be_bytes()])
+}
+
+pub fn stake_pool(state: &Pubkey, strike: u64) -> Pubkey {
+    find(&[SO_STAKE_POOL_SEED, &state.to_bytes(), &strike.to_be_bytes()])
+}
+
+pub fn stake_vault(stake_pool: &Pubkey) -> Pubkey {
+    find(&[SO_STAKE_VAULT_SEED, &stake_pool.to_bytes()])
+}
+
+pub fn stake_entry(stake_pool: &Pubkey, owner: &Pubkey) -> Pubkey {
+    find(&[
+        SO_STAKE_ENTRY_SEED,
+        &stake_pool.to_bytes(),
+        &owner.to_bytes(),
+    ])
+}
+
+pub fn event_authority() -> Pubkey {
+    find(&[EVENT_AUTHORITY_SEED])
+}
+
+// Metaplex metadata account written by name_token.
+pub fn option_mint_metadata(option_mint: &Pubkey) -> Pubkey {
+    mpl_token_metadata::pda::find_metadata_account(option_mint).0
+}
+
+// Where fees in this mint are sent.
+pub fn fee_account(mint: &Pubkey) -> Pubkey {
+    associated_token(&dual_dao(), mint)
+}
+
+pub fn dual_dao() -> Pubkey {
+    DUAL_DAO_ADDRESS.parse().unwrap()
+}
+
+pub fn associated_token(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, mint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_authority_matches_program_constant() {
+        let (address, bump) =
+            Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], &staking_options::ID);
+        assert_eq!(address, staking_options::EVENT_AUTHORITY);
+        assert_eq!(bump, staking_options::EVENT_AUTHORITY_BUMP);
+        assert_eq!(event_authority(), staking_options::EVENT_AUTHORITY);
+    }
+
+    // Same address as the legacy State fixture in tests/fixtures.
+    #[test]
+    fn state_matches_fixture() {
+        let base_mint: Pubkey = "2B7ZQCQEYSrn71WMgevKuYWzf2v7a2ER7ZNReoWBSWwp"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            state("LEGACY", &base_mint),
+            "EzqxbiNBpdS9jUFrNKMeTHbpY3wcFToms3CaGk47JBnW"
+                .parse()
+                .unwrap()
+        );
+    }
+
+    // The program seeds strikes with their big endian bytes.
+    #[test]
+    fn strike_seeds_are_big_endian() {
+        let state: Pubkey = Pubkey::new_unique();
+        let strike: u64 = 1_500_000;
+        assert_eq!(
+            option_mint(&state, strike),
+            Pubkey::find_program_address(
+                &[SO_MINT_SEED, &state.to_bytes(), &strike.to_be_bytes()],
+                &staking_options::ID
+            )
+            .0
+        );
+        assert_ne!(option_mint(&state, strike), option_mint(&state, strike + 1));
+        assert_ne!(option_mint(&state, strike), strike_state(&state, strike));
+    }
+}
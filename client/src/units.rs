@@ -0,0 +1,251 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use staking_options::State;
+
+// Amounts in State are atoms. Strikes are quote atoms per lot, where a lot is
+// lot_size atoms of the base token. Conversions to lots round down, matching
+// the program.
+//
+// Amounts going into the program are parsed from decimal strings with integer
+// math, so they are exact and never silently wrap. Amounts coming out are f64
+// since they are only for display.
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnitsError {
+    // Not a plain decimal like 12 or 0.5.
+    InvalidNumber(String),
+    // More fractional digits than the token has decimals.
+    TooPrecise(String, u8),
+    // Does not fit in a u64 of atoms.
+    OutOfRange(String),
+}
+
+impl fmt::Display for UnitsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnitsError::InvalidNumber(value) => write!(f, "{} is not a decimal number", value),
+            UnitsError::TooPrecise(value, decimals) => {
+                write!(f, "{} has more than {} decimals", value, decimals)
+            }
+            UnitsError::OutOfRange(value) => write!(f, "{} is out of range", value),
+        }
+    }
+}
+
+impl std::error::Error for UnitsError {}
+
+// Splits a decimal string into its digits as an integer and the number of
+// digits after the point, so 1.25 is (125, 2). Trailing zeros after the point
+// are dropped.
+fn parse_decimal(value: &str) -> Result<(u128, u32), UnitsError> {
+    let invalid = || UnitsError::InvalidNumber(value.to_string());
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if whole.len() + fraction.len() == 0
+        || !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let fraction: &str = fraction.trim_end_matches('0');
+
+    let mut digits: u128 = 0;
+    for c in whole.chars().chain(fraction.chars()) {
+        digits = digits
+            .checked_mul(10)
+            .and_then(|digits| digits.checked_add(c.to_digit(10).unwrap() as u128))
+            .ok_or_else(|| UnitsError::OutOfRange(value.to_string()))?;
+    }
+    Ok((digits, fraction.len() as u32))
+}
+
+fn pow10(exponent: u32, value: &str) -> Result<u128, UnitsError> {
+    10u128
+        .checked_pow(exponent)
+        .ok_or_else(|| UnitsError::OutOfRange(value.to_string()))
+}
+
+pub fn tokens_to_atoms(tokens: &str, decimals: u8) -> Result<u64, UnitsError> {
+    let (digits, scale) = parse_decimal(tokens)?;
+    if scale > decimals as u32 {
+        return Err(UnitsError::TooPrecise(tokens.to_string(), decimals));
+    }
+    digits
+        .checked_mul(pow10(decimals as u32 - scale, tokens)?)
+        .and_then(|atoms| u64::try_from(atoms).ok())
+        .ok_or_else(|| UnitsError::OutOfRange(tokens.to_string()))
+}
+
+pub fn atoms_to_tokens(atoms: u64, decimals: u8) -> f64 {
+    atoms as f64 / 10f64.powi(decimals as i32)
+}
+
+pub fn atoms_to_lots(atoms: u64, lot_size: u64) -> u64 {
+    atoms / lot_size
+}
+
+pub fn lots_to_atoms(lots: u64, lot_size: u64) -> Option<u64> {
+    lots.checked_mul(lot_size)
+}
+
+// Price of one whole base token in whole quote tokens.
+pub fn strike_to_price(strike: u64, lot_size: u64, base_decimals: u8, quote_decimals: u8) -> f64 {
+    let quote_per_lot: f64 = atoms_to_tokens(strike, quote_decimals);
+    let base_per_lot: f64 = atoms_to_tokens(lot_size, base_decimals);
+    quote_per_lot / base_per_lot
+}
+
+// Inverse of strike_to_price. Prices that are not a whole number of quote
+// atoms per lot are rounded to the nearest one.
+pub fn price_to_strike(
+    price: &str,
+    lot_size: u64,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Result<u64, UnitsError> {
+    let out_of_range = || UnitsError::OutOfRange(price.to_string());
+    let (digits, scale) = parse_decimal(price)?;
+
+    // strike = price * lot_size * 10^quote_decimals / 10^base_decimals
+    let numerator: u128 = digits
+        .checked_mul(lot_size as u128)
+        .and_then(|n| n.checked_mul(pow10(quote_decimals as u32, price).ok()?))
+        .ok_or_else(out_of_range)?;
+    let denominator: u128 = pow10(scale, price)?
+        .checked_mul(pow10(base_decimals as u32, price)?)
+        .ok_or_else(out_of_range)?;
+    let strike: u128 = numerator
+        .checked_add(denominator / 2)
+        .ok_or_else(out_of_range)?
+        / denominator;
+    u64::try_from(strike).map_err(|_| out_of_range())
+}
+
+// State does not store the quote decimals, so they are passed in from the
+// quote mint.
+pub struct SoUnits {
+    pub lot_size: u64,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+}
+
+impl SoUnits {
+    pub fn new(state: &State, quote_decimals: u8) -> Self {
+        SoUnits {
+            lot_size: state.lot_size,
+            base_decimals: state.base_decimals,
+            quote_decimals,
+        }
+    }
+
+    pub fn base_tokens_to_lots(&self, tokens: &str) -> Result<u64, UnitsError> {
+        Ok(atoms_to_lots(
+            tokens_to_atoms(tokens, self.base_decimals)?,
+            self.lot_size,
+        ))
+    }
+
+    pub fn lots_to_base_tokens(&self, lots: u64) -> Option<f64> {
+        lots_to_atoms(lots, self.lot_size).map(|atoms| atoms_to_tokens(atoms, self.base_decimals))
+    }
+
+    pub fn strike_to_price(&self, strike: u64) -> f64 {
+        strike_to_price(
+            strike,
+            self.lot_size,
+            self.base_decimals,
+            self.quote_decimals,
+        )
+    }
+
+    pub fn price_to_strike(&self, price: &str) -> Result<u64, UnitsError> {
+        price_to_strike(
+            price,
+            self.lot_size,
+            self.base_decimals,
+            self.quote_decimals,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_to_atoms_is_exact() {
+        assert_eq!(tokens_to_atoms("1", 6), Ok(1_000_000));
+        assert_eq!(tokens_to_atoms("0.000001", 6), Ok(1));
+        assert_eq!(tokens_to_atoms("1.10", 1), Ok(11));
+        assert_eq!(tokens_to_atoms(".5", 2), Ok(50));
+        assert_eq!(tokens_to_atoms("0", 9), Ok(0));
+        // Not representable exactly as an f64.
+        assert_eq!(tokens_to_atoms("0.29", 9), Ok(290_000_000));
+        assert_eq!(tokens_to_atoms("18446744073709551615", 0), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn tokens_to_atoms_errors() {
+        assert_eq!(
+            tokens_to_atoms("0.0000001", 6),
+            Err(UnitsError::TooPrecise("0.0000001".to_string(), 6))
+        );
+        assert_eq!(
+            tokens_to_atoms("18446744073709551616", 0),
+            Err(UnitsError::OutOfRange("18446744073709551616".to_string()))
+        );
+        assert_eq!(
+            tokens_to_atoms("100000000000000000000000000000000000000000", 0),
+            Err(UnitsError::OutOfRange(
+                "100000000000000000000000000000000000000000".to_string()
+            ))
+        );
+        for invalid in ["", ".", "-1", "1e6", "1.2.3", " 1", "NaN", "inf"] {
+            assert_eq!(
+                tokens_to_atoms(invalid, 6),
+                Err(UnitsError::InvalidNumber(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn price_to_strike_round_trips() {
+        // 9 decimal base in lots of 1 token, 6 decimal quote.
+        let units = SoUnits {
+            lot_size: 1_000_000_000,
+            base_decimals: 9,
+            quote_decimals: 6,
+        };
+        assert_eq!(units.price_to_strike("1.5"), Ok(1_500_000));
+        assert_eq!(units.strike_to_price(1_500_000), 1.5);
+        assert_eq!(units.base_tokens_to_lots("2.5"), Ok(2));
+        assert_eq!(units.lots_to_base_tokens(2), Some(2.0));
+    }
+
+    #[test]
+    fn price_to_strike_rounds_to_nearest_atom() {
+        // A lot is 0.001 of a 6 decimal base, so each quote atom per lot is
+        // 0.001 of a 6 decimal quote token per base token.
+        assert_eq!(price_to_strike("0.0004", 1_000, 6, 6), Ok(0));
+        assert_eq!(price_to_strike("0.0005", 1_000, 6, 6), Ok(1));
+        assert_eq!(price_to_strike("2.0014", 1_000, 6, 6), Ok(2_001));
+    }
+
+    #[test]
+    fn price_to_strike_errors() {
+        assert_eq!(
+            price_to_strike("1e3", 1, 0, 0),
+            Err(UnitsError::InvalidNumber("1e3".to_string()))
+        );
+        assert_eq!(
+            price_to_strike("18446744073709551616", 1, 0, 0),
+            Err(UnitsError::OutOfRange("18446744073709551616".to_string()))
+        );
+        assert_eq!(
+            price_to_strike("1", u64::MAX, 0, 30),
+            Err(UnitsError::OutOfRange("1".to_string()))
+        );
+    }
+}
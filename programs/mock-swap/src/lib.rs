@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+declare_id!("9uewxTUnazkw3ay9zWuENECsMb8QCxf7YpJYpnMzFntt");
+
+pub const POOL_SEED: &[u8] = b"pool";
+
+// Stand in for an AMM when testing flash exercise. Swaps at whatever rate the
+// caller asks for, out of token accounts owned by the pool PDA.
+#[program]
+pub mod mock_swap {
+    use super::*;
+
+    pub fn swap(ctx: Context<Swap>, amount_in: u64, amount_out: u64) -> Result<()> {
+        anchor_spl::token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.user_source.to_account_info(),
+                    to: ctx.accounts.pool_source.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            amount_in,
+        )?;
+
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Transfer {
+                    from: ctx.accounts.pool_destination.to_account_info(),
+                    to: ctx.accounts.user_destination.to_account_info(),
+                    authority: ctx.accounts.pool_authority.to_account_info(),
+                },
+                &[&[POOL_SEED, &[*ctx.bumps.get("pool_authority").unwrap()]]],
+            ),
+            amount_out,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Swap<'info> {
+    pub user: Signer<'info>,
+
+    #[account(mut)]
+    pub user_source: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub user_destination: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::authority = pool_authority)]
+    pub pool_source: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, token::authority = pool_authority)]
+    pub pool_destination: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Signs for the pool token accounts.
+    #[account(seeds = [POOL_SEED], bump)]
+    pub pool_authority: UncheckedAccount<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
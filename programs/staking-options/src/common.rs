@@ -1,12 +1,47 @@
 use anchor_lang::prelude::*;
-use std::cmp;
+
+use crate::errors::SOErrorCode;
 
 pub const SO_CONFIG_SEED: &[u8] = b"so-config";
 pub const SO_VAULT_SEED: &[u8] = b"so-vault";
 pub const SO_REVERSE_VAULT_SEED: &[u8] = b"so-reverse-vault";
+pub const SO_PUT_VAULT_SEED: &[u8] = b"so-put-vault";
 pub const SO_MINT_SEED: &[u8] = b"so-mint";
 pub const DUAL_DAO_ADDRESS: &str = "7Z36Efbt7a4nLiV7s5bY7J2e4TJ6V9JEKGccsy2od2bE";
 pub const SO_REVERSE_MINT_SEED: &[u8] = b"so-reverse-mint";
+pub const FEE_SCHEDULE_SEED: &[u8] = b"fee-schedule";
+pub const SO_STRIKE_SEED: &[u8] = b"so-strike";
+pub const SO_GRANT_SEED: &[u8] = b"so-grant";
+pub const SO_AUTO_EXERCISE_SEED: &[u8] = b"so-auto-exercise";
+pub const SWAP_WHITELIST_SEED: &[u8] = b"swap-whitelist";
+pub const SO_AUCTION_SEED: &[u8] = b"so-auction";
+pub const SO_SHOP_SEED: &[u8] = b"so-shop";
+pub const SO_MERKLE_SEED: &[u8] = b"so-merkle";
+pub const SO_STAKE_POOL_SEED: &[u8] = b"so-stake-pool";
+pub const SO_STAKE_VAULT_SEED: &[u8] = b"so-stake-vault";
+pub const SO_STAKE_ENTRY_SEED: &[u8] = b"so-stake-entry";
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+// Auto exercise can be cranked in this many seconds before expiration.
+pub const AUTO_EXERCISE_WINDOW_SEC: u64 = 24 * 60 * 60;
+
+// Only the first strikes are kept on the State for monitoring. StrikeState
+// accounts are not limited.
+pub const MAX_MONITORED_STRIKES: usize = 100;
+
+// Stakers have this long after a stake pool ends to claim before the
+// authority can close it without every stake claimed.
+pub const STAKE_POOL_CLAIM_GRACE_SEC: u64 = 7 * 24 * 60 * 60;
+
+// Layout version of State. Accounts created before versioning read as 0 and
+// can be brought up to date with migrate_state.
+pub const STATE_VERSION: u8 = 6;
+
+// Most Bermudan exercise windows an SO can have.
+pub const MAX_EXERCISE_WINDOWS: usize = 8;
+
+// Most breakpoints a strike schedule can have.
+pub const MAX_STRIKE_BREAKPOINTS: usize = 16;
 
 #[account]
 pub struct State {
@@ -18,7 +53,8 @@ pub struct State {
     // PDA or owner of the project.
     pub authority: Pubkey,
 
-    // Number of tokens available for SOs. Units are atoms of the base.
+    // Number of tokens available for SOs. Units are atoms of the base for calls
+    // and atoms of the quote for puts.
     pub options_available: u64,
 
     // Seconds since unix epoch for options to expire.
@@ -39,7 +75,8 @@ pub struct State {
     // Mint of the quote token
     pub quote_mint: Pubkey,
 
-    // The account that will receive payments on the options.
+    // The account that will receive payments on the options. For puts, this
+    // is the base token account that receives the delivered base.
     pub quote_account: Pubkey,
 
     // Number of atoms of the base token to be traded per lot.
@@ -48,7 +85,8 @@ pub struct State {
     pub state_bump: u8,
     pub vault_bump: u8,
 
-    // Vector of all strikes for an SO. Limit 100. For monitoring only.
+    // Vector of the first 100 strikes for an SO. For monitoring only. Every
+    // strike also has a StrikeState, which is not limited.
     // A strike is number of quote atoms per lot.
     pub strikes: Vec<u64>,
 
@@ -56,118 +94,716 @@ pub struct State {
     // authority above. This is useful in the case where a DAO is the one doing
     // the config, initStrike, withdraw, but a program is doing the issuing.
     pub issue_authority: Pubkey,
+
+    // Type of deal, which determines the fee tier. Only the fee schedule
+    // authority can change it from Standard.
+    pub deal_type: DealType,
+
+    // Layout version this account was created or migrated with.
+    pub version: u8,
+
+    // Seconds since unix epoch before which options cannot be exercised. Zero
+    // allows exercise any time before expiration.
+    pub exercise_start: u64,
+
+    // Number of decimals for the option mints. Lots are still counted in
+    // atoms of the option token.
+    pub option_decimals: u8,
+
+    // Calls are backed by base tokens in the base vault. Puts are backed by
+    // quote tokens in the put vault.
+    pub option_type: OptionType,
+    pub put_vault_bump: u8,
+
+    // Pyth price account for the base token priced in the quote token. Used
+    // for settling against the market price. Default when not set.
+    pub oracle: Pubkey,
+
+    // Oldest oracle price in seconds that is accepted.
+    pub oracle_max_age_sec: u64,
+
+    // If not empty, options can only be exercised inside one of these windows.
+    // Sorted and not overlapping.
+    pub exercise_windows: Vec<ExerciseWindow>,
+
+    // Discount on the oracle price when valuing base withheld to pay the
+    // strike in net exercise.
+    pub net_exercise_haircut_bps: u16,
+
+    // Set once options are issued or collateral is set aside for them in a
+    // sale, distribution or grant. Terms holders rely on are locked after.
+    pub options_committed: bool,
+}
+
+impl State {
+    pub const SPACE: usize = 8 + // discriminator
+        64 + // so_name
+        32 + // authority
+        8 + // options_available
+        8 + // option_expiration
+        8 + // subscription_period_end
+        8 + // decimals
+        32 + // base_mint
+        32 + // quote_mint
+        32 + // quote_account
+        8 + // lot size
+        1 + 1 + // bumps
+        8 + // strikes overhead
+        8 * 100 + // strikes
+        32 + // issue_authority
+        1 + // deal_type
+        1 + // version
+        8 + // exercise_start
+        1 + // option_decimals
+        1 + // option_type
+        1 + // put_vault_bump
+        32 + // oracle
+        8 + // oracle_max_age_sec
+        4 + MAX_EXERCISE_WINDOWS * 16 + // exercise_windows
+        2 + // net_exercise_haircut_bps
+        1 + // options_committed
+        8; // unused bytes for future upgrades
+
+    // Collateral that backs amount_lots options at the strike. Units are the
+    // same as options_available.
+    pub fn collateral_for_lots(&self, amount_lots: u64, strike: u64) -> u64 {
+        match self.option_type {
+            OptionType::Call => amount_lots.checked_mul(self.lot_size).unwrap(),
+            OptionType::Put => amount_lots.checked_mul(strike).unwrap(),
+        }
+    }
+
+    // Whether the time is inside one of the exercise windows. Always true when
+    // there are no windows.
+    pub fn in_exercise_window(&self, now: u64) -> bool {
+        self.exercise_windows.is_empty()
+            || self
+                .exercise_windows
+                .iter()
+                .any(|window| window.start <= now && now <= window.end)
+    }
+
+    // Signers that can issue, and so can also sell, distribute or grant
+    // options.
+    pub fn is_issuer(&self, key: Pubkey) -> bool {
+        key == self.authority || key == self.issue_authority
+    }
+
+    // Sets aside the collateral for lots that are handed out later by a sale,
+    // distribution or grant, so those options are always backed. Also locks
+    // the terms of the strike.
+    pub fn reserve_collateral(
+        &mut self,
+        strike_state: &mut StrikeState,
+        amount_lots: u64,
+    ) -> Result<()> {
+        let collateral: u64 = self.collateral_for_lots(amount_lots, strike_state.strike);
+        require!(
+            self.options_available >= collateral,
+            SOErrorCode::NotEnoughTokens
+        );
+        self.options_available = self.options_available.checked_sub(collateral).unwrap();
+        self.options_committed = true;
+        strike_state.lots_reserved = strike_state.lots_reserved.checked_add(amount_lots).unwrap();
+
+        Ok(())
+    }
+
+    // Returns the collateral for reserved lots that were never handed out.
+    // Only valid before expiration, after which the vault is withdrawn and
+    // there is nothing to return to.
+    pub fn release_collateral(&mut self, amount_lots: u64, strike: u64) {
+        let collateral: u64 = self.collateral_for_lots(amount_lots, strike);
+        self.options_available = self.options_available.checked_add(collateral).unwrap();
+    }
+}
+
+// Inclusive range of seconds since unix epoch in which options can be
+// exercised.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ExerciseWindow {
+    pub start: u64,
+    pub end: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OptionType {
+    // Holder pays strike in quote and receives base.
+    Call,
+    // Holder delivers base and receives strike in quote.
+    Put,
+}
+
+// Accounting for a single strike of an SO.
+#[account]
+pub struct StrikeState {
+    // The SO State this strike belongs to.
+    pub state: Pubkey,
+
+    // Number of quote atoms per lot.
+    pub strike: u64,
+
+    pub bump: u8,
+    pub mint_bump: u8,
+
+    // Zero when the strike is not reversible.
+    pub reverse_mint_bump: u8,
+
+    // Lots minted to users.
+    pub lots_issued: u64,
+
+    // Lots exercised, either regular or reversible.
+    pub lots_exercised: u64,
+
+    // Lots that were reverse exercised back into options.
+    pub lots_reversed: u64,
+
+    // Seconds since unix epoch when the strike was created.
+    pub created_at: u64,
+
+    // If not empty, the strike charged on exercise changes over time. The
+    // strike in the seeds is used before the first breakpoint.
+    pub schedule_kind: StrikeScheduleKind,
+    pub strike_schedule: Vec<StrikeBreakpoint>,
+
+    // Price in quote atoms per lot above which the payoff does not grow. Zero
+    // when uncapped. Capped strikes are settled with the oracle.
+    pub cap: u64,
+
+    // Exercise depends on whether the oracle price has crossed the barrier.
+    // Price is in quote atoms per lot.
+    pub barrier_kind: BarrierKind,
+    pub barrier_price: u64,
+    pub barrier_oracle: Pubkey,
+    pub barrier_triggered: bool,
+
+    // Lots promised in vesting grants, claimed or not. Less what was revoked
+    // before vesting.
+    pub lots_granted: u64,
+
+    // Lots set aside for sales, distributions, stake pools and grants. Not
+    // reduced when unused lots are released, since holders may already have
+    // bought or claimed at these terms.
+    pub lots_reserved: u64,
 }
 
-const DUAL_RISK_MANAGER: &str = "CkcJx7Uwgxck5zm3DqUp2N1ikkkoPn2wA8zf7oS4tFSZ";
+impl StrikeState {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        1 + 1 + 1 + // bumps
+        8 + // lots_issued
+        8 + // lots_exercised
+        8 + // lots_reversed
+        8 + // created_at
+        1 + // schedule_kind
+        4 + // strike_schedule, breakpoints are added with realloc
+        8 + // cap
+        1 + // barrier_kind
+        8 + // barrier_price
+        32 + // barrier_oracle
+        1 + // barrier_triggered
+        8 + // lots_granted
+        8 + // lots_reserved
+        1; // unused bytes for future upgrades
+
+    // Holders need to know the terms before they get options, so terms of the
+    // strike are fixed once any are issued or reserved for a sale,
+    // distribution, stake pool or grant.
+    pub fn terms_locked(&self) -> bool {
+        self.lots_issued > 0 || self.lots_reserved > 0
+    }
 
-const USDC: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
-const USDT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
-const DAIPO: &str = "EjmyN6qEC1Tf1JxiG1ae7UTJhUxSwk1TCWNWqxWV4J6o";
-const USDH: &str = "USDH1SM1ojwWUga67PGrgFWUHibbjqMvuMaDkRJTgkX";
-const CHAI: &str = "3jsFX1tx2Z8ewmamiwSU851GzyzM2DJMq7KWW5DM8Py3";
+    // Strike in effect at the time in quote atoms per lot.
+    pub fn strike_at(&self, now: u64) -> u64 {
+        let next = self
+            .strike_schedule
+            .iter()
+            .position(|breakpoint| breakpoint.timestamp > now)
+            .unwrap_or(self.strike_schedule.len());
+        if next == 0 {
+            return self.strike;
+        }
+        let previous = &self.strike_schedule[next - 1];
+        if self.schedule_kind == StrikeScheduleKind::Step || next == self.strike_schedule.len() {
+            return previous.strike;
+        }
+
+        // Interpolate between the breakpoints on either side.
+        let following = &self.strike_schedule[next];
+        let elapsed = (now - previous.timestamp) as i128;
+        let duration = (following.timestamp - previous.timestamp) as i128;
+        let change = following.strike as i128 - previous.strike as i128;
+        (previous.strike as i128 + change * elapsed / duration) as u64
+    }
 
-const WBTCPO: &str = "3NZ9JMVBmGAqocybic2c7LQCJScmgsAZ6vQqTDzcqmJh";
-const TBTC: &str = "6DNSN2BJsaPFdFFc1zP37kkeNe4Usc1Sqkzr9C9vPWcU";
-const WSTETHPO: &str = "ZScHuTtqZukUrtZS43teTKGs2VqkKL8k4QCouR2n6Uo";
-const RETHPO: &str = "9UV2pC1qPaVMfRv8CF7qhv7ihbzR91pr2LX9y2FDfGLy";
-const WETHPO: &str = "7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs";
-const WSOL: &str = "So11111111111111111111111111111111111111112";
+    // Knock in barriers need to be triggered before exercise, knock out
+    // barriers prevent exercise once triggered.
+    pub fn barrier_allows_exercise(&self) -> bool {
+        match self.barrier_kind {
+            BarrierKind::None => true,
+            BarrierKind::UpAndOut | BarrierKind::DownAndOut => !self.barrier_triggered,
+            BarrierKind::UpAndIn | BarrierKind::DownAndIn => self.barrier_triggered,
+        }
+    }
 
-const MNGO: &str = "MangoCzJ36AjZyKwVj3VnYU4GTonjfVEnJmvvWaxLac";
-const RAY: &str = "4k3Dyjzvzp8eMZWUXbBCjEvwSkkk59S5iCNLY3QrkX6R";
-const NOS: &str = "nosXBVoaCTtYdLvKY6Csb4AC8JCdQKKAaWYtx2ZMoo7";
+    // Price used for the payoff given the oracle price per lot.
+    pub fn capped_price(&self, price: u64) -> u64 {
+        if self.cap == 0 {
+            price
+        } else {
+            std::cmp::min(price, self.cap)
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StrikeScheduleKind {
+    // The strike of the latest breakpoint applies until the next one.
+    Step,
+    // The strike moves linearly between breakpoints.
+    Linear,
+}
 
-pub fn is_fee_exempt(user_quote_account_owner: Pubkey) -> bool {
-    // Do not charge fee when the DUAL DAO is exercising since that is the recipient of the fee.
-    if user_quote_account_owner.to_string() == DUAL_DAO_ADDRESS {
-        return true;
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BarrierKind {
+    None,
+    // Triggered when the price is at or above the barrier.
+    UpAndOut,
+    UpAndIn,
+    // Triggered when the price is at or below the barrier.
+    DownAndOut,
+    DownAndIn,
+}
+
+impl BarrierKind {
+    pub fn is_up(&self) -> bool {
+        *self == BarrierKind::UpAndOut || *self == BarrierKind::UpAndIn
     }
-    // Do not charge fee when the DUAL DAO is exercising since that is the recipient of the fee.
-    if user_quote_account_owner.to_string() == DUAL_RISK_MANAGER {
-        return true;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StrikeBreakpoint {
+    // Seconds since unix epoch.
+    pub timestamp: u64,
+    // Quote atoms per lot.
+    pub strike: u64,
+}
+
+// Options granted to a single recipient that are minted as they vest. The
+// collateral for all lots is reserved from options_available when the grant is
+// created.
+#[account]
+pub struct VestingGrant {
+    pub state: Pubkey,
+    pub strike: u64,
+    pub recipient: Pubkey,
+    pub bump: u8,
+
+    // Lots that will be vested at the end, including ones already claimed.
+    pub total_lots: u64,
+    pub claimed_lots: u64,
+
+    // Seconds since unix epoch. Nothing vests before the cliff, then lots vest
+    // linearly from start until end.
+    pub start: u64,
+    pub cliff: u64,
+    pub end: u64,
+}
+
+impl VestingGrant {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        32 + // recipient
+        1 + // bump
+        8 + // total_lots
+        8 + // claimed_lots
+        8 + // start
+        8 + // cliff
+        8 + // end
+        64; // unused bytes for future upgrades
+
+    pub fn vested_lots(&self, now: u64) -> u64 {
+        if now < self.cliff {
+            return 0;
+        }
+        if now >= self.end {
+            return self.total_lots;
+        }
+        ((self.total_lots as u128)
+            .checked_mul(now.checked_sub(self.start).unwrap() as u128)
+            .unwrap()
+            .checked_div(self.end.checked_sub(self.start).unwrap() as u128)
+            .unwrap()) as u64
     }
+}
 
-    return false;
+// Registration of a holder to have their options exercised for them shortly
+// before expiration. This account is the token delegate on the holder's
+// option and quote accounts.
+#[account]
+pub struct AutoExercise {
+    pub state: Pubkey,
+    pub strike: u64,
+    pub owner: Pubkey,
+    pub bump: u8,
+
+    // Accounts of the owner that are used when cranking.
+    pub user_so_account: Pubkey,
+    pub user_quote_account: Pubkey,
+    pub user_base_account: Pubkey,
 }
 
-pub fn get_fee_bps(base_mint: Pubkey, quote_mint: Pubkey, name: String) -> u64 {
-    let is_base_stable = [
-        USDC.to_string(),
-        USDT.to_string(),
-        DAIPO.to_string(),
-        USDH.to_string(),
-        CHAI.to_string(),
-    ]
-    .contains(&base_mint.to_string());
-    let is_quote_stable = [
-        USDC.to_string(),
-        USDT.to_string(),
-        DAIPO.to_string(),
-        USDH.to_string(),
-        CHAI.to_string(),
-    ]
-    .contains(&quote_mint.to_string());
+impl AutoExercise {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        32 + // owner
+        1 + // bump
+        32 + // user_so_account
+        32 + // user_quote_account
+        32 + // user_base_account
+        32; // unused bytes for future upgrades
+}
 
-    // Hack for MM deals. Name is a user generated field, but if another user
-    // were to abuse that, we will address later in future versions.
-    let is_mm_deal = name.to_lowercase().contains("mm") || name.to_lowercase().contains("loan");
+// Dutch auction selling options on a strike. The collateral for all lots is
+// reserved from options_available when the auction is created.
+#[account]
+pub struct Auction {
+    pub state: Pubkey,
+    pub strike: u64,
+    pub bump: u8,
+
+    pub lots_for_sale: u64,
+    pub lots_sold: u64,
+
+    // Seconds since unix epoch. Lots can be bought between start and end.
+    pub start_time: u64,
+    pub end_time: u64,
+
+    // Quote atoms per lot. The price decays from start_price at start_time to
+    // floor_price.
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub decay: AuctionDecay,
+}
 
-    let is_otc = name.to_lowercase().contains("otc");
+impl Auction {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        1 + // bump
+        8 + // lots_for_sale
+        8 + // lots_sold
+        8 + // start_time
+        8 + // end_time
+        8 + // start_price
+        8 + // floor_price
+        1 + 8 + // decay
+        64; // unused bytes for future upgrades
+
+    // Price per lot in quote atoms at the time.
+    pub fn price_at(&self, now: u64) -> u64 {
+        if now <= self.start_time {
+            return self.start_price;
+        }
+        if now >= self.end_time {
+            return self.floor_price;
+        }
+        let elapsed: u64 = now - self.start_time;
+        let range: u64 = self.start_price - self.floor_price;
+        let above_floor: u64 = match self.decay {
+            AuctionDecay::Linear => {
+                let duration: u64 = self.end_time - self.start_time;
+                range - ((range as u128 * elapsed as u128) / duration as u128) as u64
+            }
+            AuctionDecay::Exponential { half_life_sec } => {
+                // Halve for every full half life, and interpolate linearly
+                // within the current one.
+                let halvings: u64 = elapsed / half_life_sec;
+                if halvings >= 64 {
+                    0
+                } else {
+                    let current: u64 = range >> halvings;
+                    let next: u64 = current >> 1;
+                    let into_half_life: u64 = elapsed % half_life_sec;
+                    current
+                        - (((current - next) as u128 * into_half_life as u128)
+                            / half_life_sec as u128) as u64
+                }
+            }
+        };
+
+        self.floor_price + above_floor
+    }
+}
 
-    let is_base_major = [
-        WBTCPO.to_string(),
-        TBTC.to_string(),
-        WETHPO.to_string(),
-        WSTETHPO.to_string(),
-        RETHPO.to_string(),
-        WSOL.to_string(),
-    ]
-    .contains(&base_mint.to_string());
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AuctionDecay {
+    // Straight line from the start price to the floor at the end time.
+    Linear,
+    // Distance to the floor halves every half life.
+    Exponential { half_life_sec: u64 },
+}
 
-    let is_quote_major = [
-        WBTCPO.to_string(),
-        TBTC.to_string(),
-        WETHPO.to_string(),
-        WSTETHPO.to_string(),
-        RETHPO.to_string(),
-        WSOL.to_string(),
-    ]
-    .contains(&quote_mint.to_string());
+// Fixed price sale of options on a strike. The collateral for all lots is
+// reserved from options_available when the shop is created.
+#[account]
+pub struct Shop {
+    pub state: Pubkey,
+    pub strike: u64,
+    pub bump: u8,
 
-    let is_base_partner =
-        [MNGO.to_string(), RAY.to_string(), NOS.to_string()].contains(&base_mint.to_string());
+    // Quote atoms per lot.
+    pub premium_per_lot: u64,
 
-    let is_quote_partner =
-        [MNGO.to_string(), RAY.to_string(), NOS.to_string()].contains(&quote_mint.to_string());
+    pub max_lots: u64,
+    pub lots_sold: u64,
+}
 
-    let mut fee_bps = 350;
+impl Shop {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        1 + // bump
+        8 + // premium_per_lot
+        8 + // max_lots
+        8 + // lots_sold
+        64; // unused bytes for future upgrades
+}
 
-    // Reduced fee on stables
-    if is_base_stable && is_quote_stable {
-        fee_bps = cmp::min(fee_bps, 5);
+// Airdrop of options on a strike. Recipients prove (index, address, lots)
+// against the root and claim once. The collateral for all lots is reserved
+// from options_available when the distributor is created.
+#[account]
+pub struct MerkleDistributor {
+    pub state: Pubkey,
+    pub strike: u64,
+    pub bump: u8,
+
+    pub root: [u8; 32],
+    pub num_leaves: u64,
+    pub total_lots: u64,
+    pub lots_claimed: u64,
+
+    // One bit per leaf index, set once that leaf has been claimed.
+    pub claimed_bitmap: Vec<u8>,
+}
+
+impl MerkleDistributor {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        1 + // bump
+        32 + // root
+        8 + // num_leaves
+        8 + // total_lots
+        8 + // lots_claimed
+        4 + // claimed_bitmap length
+        64; // unused bytes for future upgrades
+
+    pub fn bitmap_len(num_leaves: u64) -> usize {
+        ((num_leaves + 7) / 8) as usize
     }
 
-    // Reduced fee on mm tokens
-    if is_mm_deal {
-        fee_bps = cmp::min(fee_bps, 25);
+    pub fn is_claimed(&self, index: u64) -> bool {
+        self.claimed_bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0
     }
 
-    if is_otc {
-        fee_bps = cmp::min(fee_bps, 10);
+    pub fn set_claimed(&mut self, index: u64) {
+        self.claimed_bitmap[(index / 8) as usize] |= 1 << (index % 8);
     }
+}
+
+// Staking subscription on a strike. Stakers deposit stake_mint during the
+// subscription period and earn a share of total_lots proportional to their
+// stake seconds, claimable once the subscription period ends. The collateral
+// for all lots is reserved from options_available when the pool is created.
+#[account]
+pub struct StakePool {
+    pub state: Pubkey,
+    pub strike: u64,
+    pub bump: u8,
+
+    pub stake_mint: Pubkey,
+    pub stake_vault_bump: u8,
+
+    pub total_lots: u64,
+    pub lots_claimed: u64,
+
+    // Stake stops accruing at end_time, which is the subscription period end.
+    pub end_time: u64,
+    pub total_staked: u64,
+    pub stake_seconds: u128,
+    pub last_update: u64,
+
+    // Stake seconds of the entries that claimed. The pool is fully claimed
+    // once this reaches stake_seconds.
+    pub stake_seconds_claimed: u128,
+    // Set by close_stake_pool. Stakers can still unstake, but not claim.
+    pub closed: bool,
+}
+
+impl StakePool {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // state
+        8 + // strike
+        1 + // bump
+        32 + // stake_mint
+        1 + // stake_vault_bump
+        8 + // total_lots
+        8 + // lots_claimed
+        8 + // end_time
+        8 + // total_staked
+        16 + // stake_seconds
+        8 + // last_update
+        16 + // stake_seconds_claimed
+        1 + // closed
+        47; // unused bytes for future upgrades
+
+    pub fn accrue(&mut self, now: u64) {
+        let until: u64 = std::cmp::min(now, self.end_time);
+        if until > self.last_update {
+            self.stake_seconds = self
+                .stake_seconds
+                .checked_add(self.total_staked as u128 * (until - self.last_update) as u128)
+                .unwrap();
+            self.last_update = until;
+        }
+    }
+}
+
+#[account]
+pub struct StakeEntry {
+    pub stake_pool: Pubkey,
+    pub owner: Pubkey,
+    pub bump: u8,
+
+    pub amount: u64,
+    pub stake_seconds: u128,
+    pub last_update: u64,
+    pub claimed: bool,
+}
 
-    // Charge reduced fees on pairs of majors.
-    if (is_base_major && is_quote_stable) || (is_quote_major && is_base_stable) {
-        fee_bps = cmp::min(fee_bps, 10);
+impl StakeEntry {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // stake_pool
+        32 + // owner
+        1 + // bump
+        8 + // amount
+        16 + // stake_seconds
+        8 + // last_update
+        1 + // claimed
+        32; // unused bytes for future upgrades
+
+    pub fn accrue(&mut self, now: u64, end_time: u64) {
+        let until: u64 = std::cmp::min(now, end_time);
+        if until > self.last_update {
+            self.stake_seconds = self
+                .stake_seconds
+                .checked_add(self.amount as u128 * (until - self.last_update) as u128)
+                .unwrap();
+            self.last_update = until;
+        }
     }
-    // Charge reduced fees to partners.
-    if (is_base_partner && is_quote_stable) || (is_quote_partner && is_base_stable) {
-        fee_bps = cmp::min(fee_bps, 25);
+
+    // Share of the pool, rounded down so the total never exceeds total_lots.
+    pub fn earned_lots(&self, stake_pool: &StakePool) -> u64 {
+        if stake_pool.stake_seconds == 0 {
+            return 0;
+        }
+        (self.stake_seconds * stake_pool.total_lots as u128 / stake_pool.stake_seconds) as u64
     }
-    // Charge lower fee on major/major pairs
-    if is_base_major && is_quote_major {
-        fee_bps = cmp::min(fee_bps, 5);
+}
+
+// Existence of this account means the DUAL DAO allows flash exercise to call
+// into the program.
+#[account]
+pub struct SwapWhitelistEntry {
+    pub program: Pubkey,
+    pub bump: u8,
+}
+
+impl SwapWhitelistEntry {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // program
+        1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DealType {
+    Standard,
+    MarketMaker,
+    Loan,
+    Otc,
+}
+
+// Fee schedule limits. These determine the size of the FeeSchedule account.
+pub const MAX_FEE_SCHEDULE_MINTS: usize = 64;
+pub const MAX_FEE_SCHEDULE_PAIRS: usize = 16;
+pub const MAX_FEE_BPS: u64 = 10_000;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MintCategory {
+    Other,
+    Stable,
+    Major,
+    Partner,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CategorizedMint {
+    pub mint: Pubkey,
+    pub category: MintCategory,
+}
+
+// Fee for a pair of categories. The order of the categories does not matter,
+// so a Major/Stable entry also covers Stable/Major.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PairFee {
+    pub category_a: MintCategory,
+    pub category_b: MintCategory,
+    pub fee_bps: u64,
+}
+
+impl PairFee {
+    pub fn matches(&self, base_category: MintCategory, quote_category: MintCategory) -> bool {
+        (self.category_a == base_category && self.category_b == quote_category)
+            || (self.category_a == quote_category && self.category_b == base_category)
     }
+}
+
+#[account]
+pub struct FeeSchedule {
+    // Only this authority can update the schedule. Set to the DUAL DAO when
+    // the schedule is created.
+    pub authority: Pubkey,
+
+    pub bump: u8,
 
-    return fee_bps;
+    // Fee charged when none of the discounts below apply.
+    pub default_fee_bps: u64,
+
+    // Discounted fees for market maker / loan deals and OTC deals.
+    pub mm_fee_bps: u64,
+    pub otc_fee_bps: u64,
+
+    // Category for every mint that gets a discount. Mints not in this list are
+    // MintCategory::Other. Limit MAX_FEE_SCHEDULE_MINTS.
+    pub mints: Vec<CategorizedMint>,
+
+    // Discounted fees for pairs of categories. Limit MAX_FEE_SCHEDULE_PAIRS.
+    pub pair_fees: Vec<PairFee>,
+}
+
+impl FeeSchedule {
+    pub fn category(&self, mint: Pubkey) -> MintCategory {
+        self.mints
+            .iter()
+            .find(|categorized_mint| categorized_mint.mint == mint)
+            .map_or(MintCategory::Other, |categorized_mint| {
+                categorized_mint.category
+            })
+    }
 }
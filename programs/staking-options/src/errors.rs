@@ -20,10 +20,58 @@ pub enum SOErrorCode {
     NotEnoughTokens,
     #[msg("Incorrect Authority")]
     IncorrectAuthority,
+    // Deprecated, strikes are no longer capped since StrikeState. Kept so the
+    // error codes after it do not shift.
     #[msg("Too many strikes")]
     TooManyStrikes,
     #[msg("Invalid expiration")]
     InvalidExpiration,
     #[msg("Invalid name")]
     InvalidName,
+    #[msg("Invalid fee schedule")]
+    InvalidFeeSchedule,
+    #[msg("Unsupported state version")]
+    InvalidVersion,
+    #[msg("Not yet exercisable")]
+    NotYetExercisable,
+    #[msg("Invalid config")]
+    InvalidConfig,
+    #[msg("Not supported for this option type")]
+    WrongOptionType,
+    #[msg("Oracle account is not a valid price feed")]
+    InvalidOracle,
+    #[msg("Oracle price is too old")]
+    StaleOracle,
+    #[msg("Option is not in the money")]
+    OutOfTheMoney,
+    #[msg("Not inside an exercise window")]
+    OutsideExerciseWindow,
+    #[msg("No vested options to claim")]
+    NothingToClaim,
+    #[msg("No options approved to exercise")]
+    NothingToExercise,
+    #[msg("Capped strikes can only be cash exercised")]
+    CashSettledOnly,
+    #[msg("Barrier does not allow exercise")]
+    BarrierBlocksExercise,
+    #[msg("Oracle price has not reached the barrier")]
+    BarrierNotReached,
+    #[msg("Payment did not arrive after the swap")]
+    FlashPaymentMissing,
+    #[msg("Auction is not active")]
+    AuctionNotActive,
+    #[msg("Price is above the maximum")]
+    PriceTooHigh,
+    #[msg("Invalid merkle proof")]
+    InvalidProof,
+    #[msg("Already claimed")]
+    AlreadyClaimed,
+    #[msg("Invalid event authority")]
+    InvalidEventAuthority,
+    #[msg("Token account is already delegated")]
+    AlreadyDelegated,
+    #[msg("Stakers can still claim from the stake pool")]
+    ClaimsOutstanding,
+    #[msg("Stake pool is closed")]
+    StakePoolClosed,
 }
@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::pubkey;
+use anchor_lang::Event;
+
+use crate::common::*;
+use crate::errors::SOErrorCode;
+pub use crate::program::StakingOptions;
+
+// Prefix of the self CPI instruction data that carries an event. Same tag and
+// layout as emit_cpi in later anchor versions, so their event parsers can read
+// these from inner instructions.
+pub const EVENT_IX_TAG_LE: [u8; 8] = 0x1d9acb512ea545e4u64.to_le_bytes();
+
+// PDA of EVENT_AUTHORITY_SEED and its bump, so emitting does not pay for
+// find_program_address. The client pda tests check these against the seed.
+pub const EVENT_AUTHORITY: Pubkey = pubkey!("7bT2cEgQuQiQH37exxfoTAQ3anApuHVCMdHcsGadZzUf");
+pub const EVENT_AUTHORITY_BUMP: u8 = 255;
+
+// Events are recorded in instruction data of a CPI into this program instead
+// of in the logs, so they are not lost when logs are truncated. Clients that
+// do not pass the event accounts get the event in the logs instead.
+pub fn emit_event<'info, E: Event>(
+    event_authority: &Option<UncheckedAccount<'info>>,
+    program: &Option<Program<'info, StakingOptions>>,
+    event: E,
+) -> Result<()> {
+    match (event_authority, program) {
+        (Some(event_authority), Some(program)) => {
+            require_keys_eq!(
+                event_authority.key(),
+                EVENT_AUTHORITY,
+                SOErrorCode::InvalidEventAuthority
+            );
+
+            let mut data: Vec<u8> = EVENT_IX_TAG_LE.to_vec();
+            data.extend(event.data());
+            invoke_signed(
+                &Instruction {
+                    program_id: crate::ID,
+                    accounts: vec![AccountMeta::new_readonly(EVENT_AUTHORITY, true)],
+                    data,
+                },
+                &[event_authority.to_account_info(), program.to_account_info()],
+                &[&[EVENT_AUTHORITY_SEED, &[EVENT_AUTHORITY_BUMP]]],
+            )?;
+        }
+        _ => emit!(event),
+    }
+
+    Ok(())
+}
+
+// Target of the self CPI in emit_event. The event is already in the
+// instruction data, so the only thing to do is reject calls that did not come
+// from this program.
+pub fn handle_event_instruction(accounts: &[AccountInfo], data: &[u8]) -> Result<()> {
+    require!(
+        data.starts_with(&EVENT_IX_TAG_LE),
+        anchor_lang::error::ErrorCode::InstructionFallbackNotFound
+    );
+    let event_authority: &AccountInfo = accounts
+        .first()
+        .ok_or(anchor_lang::error::ErrorCode::AccountNotEnoughKeys)?;
+    require!(
+        event_authority.is_signer && event_authority.key() == EVENT_AUTHORITY,
+        SOErrorCode::InvalidEventAuthority
+    );
+
+    Ok(())
+}
+
+#[event]
+pub struct SoConfigured {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub so_name: String,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub option_type: OptionType,
+    pub num_tokens: u64,
+    pub lot_size: u64,
+    pub subscription_period_end: u64,
+    pub option_expiration: u64,
+}
+
+impl SoConfigured {
+    // Read after the State is filled out, when options_available is still
+    // the number of tokens taken.
+    pub fn from_state(state: &Account<State>) -> Self {
+        SoConfigured {
+            state: state.key(),
+            authority: state.authority,
+            so_name: state.so_name.clone(),
+            base_mint: state.base_mint,
+            quote_mint: state.quote_mint,
+            option_type: state.option_type,
+            num_tokens: state.options_available,
+            lot_size: state.lot_size,
+            subscription_period_end: state.subscription_period_end,
+            option_expiration: state.option_expiration,
+        }
+    }
+}
+
+#[event]
+pub struct StrikeInitialized {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub strike: u64,
+    pub option_mint: Pubkey,
+    pub reversible: bool,
+}
+
+// authority is the signer that got the options minted, the issuer for issue
+// and the buyer or claimant for sales, airdrops, stake pools and grants.
+#[event]
+pub struct OptionsIssued {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub strike: u64,
+    pub amount_lots: u64,
+    pub recipient: Pubkey,
+}
+
+// payment is the strike in quote atoms for all lots, paid by call holders and
+// received by put holders. Zero for cash exercise, where no strike changes
+// hands. The fee is in atoms of fee_mint and amount_delivered is what the
+// exerciser receives, in base for calls and in quote for puts.
+#[event]
+pub struct Exercised {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub strike: u64,
+    pub amount_lots: u64,
+    pub payment: u64,
+    pub fee: u64,
+    pub fee_mint: Pubkey,
+    pub amount_delivered: u64,
+}
+
+#[event]
+pub struct ReversibleExercised {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub strike: u64,
+    pub amount_lots: u64,
+    pub payment: u64,
+    pub amount_delivered: u64,
+}
+
+#[event]
+pub struct ReverseExercised {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub strike: u64,
+    pub amount_lots: u64,
+    pub payment_returned: u64,
+    pub base_returned: u64,
+}
+
+#[event]
+pub struct TokensAdded {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub num_tokens_added: u64,
+    pub options_available: u64,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub base_withdrawn: u64,
+    pub quote_withdrawn: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct ExpirationModified {
+    pub state: Pubkey,
+    pub authority: Pubkey,
+    pub old_expiration: u64,
+    pub new_expiration: u64,
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use std::cmp;
+
+use crate::common::*;
+
+const DUAL_RISK_MANAGER: &str = "CkcJx7Uwgxck5zm3DqUp2N1ikkkoPn2wA8zf7oS4tFSZ";
+
+pub fn is_fee_exempt(user_quote_account_owner: Pubkey) -> bool {
+    // Do not charge fee when the DUAL DAO is exercising since that is the recipient of the fee.
+    if user_quote_account_owner.to_string() == DUAL_DAO_ADDRESS {
+        return true;
+    }
+    // Do not charge fee when the DUAL DAO is exercising since that is the recipient of the fee.
+    if user_quote_account_owner.to_string() == DUAL_RISK_MANAGER {
+        return true;
+    }
+
+    return false;
+}
+
+pub fn get_fee_bps(
+    fee_schedule: &FeeSchedule,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    deal_type: DealType,
+) -> u64 {
+    let base_category = fee_schedule.category(base_mint);
+    let quote_category = fee_schedule.category(quote_mint);
+
+    let mut fee_bps = fee_schedule.default_fee_bps;
+
+    match deal_type {
+        DealType::Standard => {}
+        // Reduced fee on mm tokens
+        DealType::MarketMaker | DealType::Loan => {
+            fee_bps = cmp::min(fee_bps, fee_schedule.mm_fee_bps);
+        }
+        DealType::Otc => {
+            fee_bps = cmp::min(fee_bps, fee_schedule.otc_fee_bps);
+        }
+    }
+
+    // Reduced fees on pairs of categories, for example stable/stable,
+    // major/stable and partner/stable.
+    for pair_fee in fee_schedule.pair_fees.iter() {
+        if pair_fee.matches(base_category, quote_category) {
+            fee_bps = cmp::min(fee_bps, pair_fee.fee_bps);
+        }
+    }
+
+    return fee_bps;
+}
+
+// Fee in quote atoms for a payment of quote atoms on this SO. This is the
+// only place fees get computed, so exercise and the settlement of reversible
+// exercises in withdraw_all always charge the same rate.
+pub fn calculate_fee(
+    fee_schedule: &FeeSchedule,
+    state: &State,
+    payer: Pubkey,
+    payment: u64,
+) -> u64 {
+    payment
+        .checked_mul(effective_fee_bps(fee_schedule, state, payer))
+        .unwrap()
+        .checked_div(10_000)
+        .unwrap()
+}
+
+// Rate calculate_fee charges this payer on this SO.
+pub fn effective_fee_bps(fee_schedule: &FeeSchedule, state: &State, payer: Pubkey) -> u64 {
+    if is_fee_exempt(payer) {
+        return 0;
+    }
+
+    get_fee_bps(
+        fee_schedule,
+        state.base_mint,
+        state.quote_mint,
+        state.deal_type,
+    )
+}
+
+// Splits a payment, sending the fee to the DUAL DAO and the rest to the
+// recipient. Every path that charges a fee transfers through here so the
+// split cannot differ between them. signer_seeds are empty when the authority
+// signed the transaction.
+pub fn transfer_payment_with_fee<'info>(
+    token_program: AccountInfo<'info>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    fee_account: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    payment: u64,
+    fee: u64,
+) -> Result<()> {
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            anchor_spl::token::Transfer {
+                from: from.clone(),
+                to,
+                authority: authority.clone(),
+            },
+            signer_seeds,
+        ),
+        payment.checked_sub(fee).unwrap(),
+    )?;
+    if fee > 0 {
+        anchor_spl::token::transfer(
+            CpiContext::new_with_signer(
+                token_program,
+                anchor_spl::token::Transfer {
+                    from,
+                    to: fee_account,
+                    authority,
+                },
+                signer_seeds,
+            ),
+            fee,
+        )?;
+    }
+
+    Ok(())
+}
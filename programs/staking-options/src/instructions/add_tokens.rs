@@ -23,6 +23,17 @@ pub fn add_tokens(ctx: Context<AddTokens>, num_tokens_to_add: u64) -> Result<()>
         .checked_add(num_tokens_to_add)
         .unwrap();
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        TokensAdded {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            num_tokens_added: num_tokens_to_add,
+            options_available: ctx.accounts.state.options_available,
+        },
+    )?;
+
     Ok(())
 }
 
@@ -54,6 +65,11 @@ pub struct AddTokens<'info> {
     pub base_account: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> AddTokens<'info> {
@@ -69,9 +85,102 @@ impl<'info> AddTokens<'info> {
             SOErrorCode::WrongMint
         );
 
+        // Puts are backed by quote tokens which are not held in the base vault.
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+
         // Do not allow adding tokens to an SO that is expired already.
         check_not_expired!(self.state.subscription_period_end);
 
         Ok(())
     }
 }
+
+// Puts are backed by quote tokens, so these go to the put vault. Same as
+// add_tokens otherwise, options_available is in quote atoms for puts.
+pub fn add_tokens_put(ctx: Context<AddTokensPut>, num_tokens_to_add: u64) -> Result<()> {
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        token::Transfer {
+            from: ctx.accounts.quote_account.to_account_info(),
+            to: ctx.accounts.put_vault.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        },
+    );
+    token::transfer(cpi_ctx, num_tokens_to_add)?;
+
+    ctx.accounts.state.options_available = ctx
+        .accounts
+        .state
+        .options_available
+        .checked_add(num_tokens_to_add)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        TokensAdded {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            num_tokens_added: num_tokens_to_add,
+            options_available: ctx.accounts.state.options_available,
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(num_tokens_to_add: u64)]
+pub struct AddTokensPut<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The quote tokens backing the puts.
+    #[account(mut,
+        seeds = [SO_PUT_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.put_vault_bump,
+    )]
+    pub put_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the additional quote tokens are coming from.
+    #[account(mut)]
+    pub quote_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> AddTokensPut<'info> {
+    pub fn validate_accounts(&self, _num_tokens_to_add: u64) -> Result<()> {
+        require_keys_eq!(
+            self.quote_account.mint,
+            self.state.quote_mint,
+            SOErrorCode::WrongMint
+        );
+
+        require!(
+            self.state.option_type == OptionType::Put,
+            SOErrorCode::WrongOptionType
+        );
+
+        check_not_expired!(self.state.subscription_period_end);
+
+        Ok(())
+    }
+}
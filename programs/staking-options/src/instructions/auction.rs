@@ -0,0 +1,343 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct AuctionParams {
+    pub lots_for_sale: u64,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub decay: AuctionDecay,
+}
+
+impl AuctionParams {
+    pub fn validate(&self, state: &State) -> Result<()> {
+        require!(self.lots_for_sale > 0, SOErrorCode::InvalidConfig);
+        require!(self.start_time < self.end_time, SOErrorCode::InvalidConfig);
+        require!(
+            self.floor_price <= self.start_price,
+            SOErrorCode::InvalidConfig
+        );
+        if let AuctionDecay::Exponential { half_life_sec } = self.decay {
+            require!(half_life_sec > 0, SOErrorCode::InvalidConfig);
+        }
+
+        // Options can only be minted during the subscription period.
+        require!(
+            self.end_time <= state.subscription_period_end,
+            SOErrorCode::InvalidConfig
+        );
+
+        Ok(())
+    }
+}
+
+// Premiums go to the project quote account. Puts store the project base
+// account on the State instead, so for those any quote account of the
+// authority is accepted.
+pub fn validate_premium_account(state: &State, account: &Account<TokenAccount>) -> Result<()> {
+    match state.option_type {
+        OptionType::Call => require_keys_eq!(
+            account.key(),
+            state.quote_account,
+            SOErrorCode::IncorrectFeeAccount
+        ),
+        OptionType::Put => require!(
+            account.owner == state.authority && account.mint == state.quote_mint,
+            SOErrorCode::IncorrectFeeAccount
+        ),
+    }
+
+    Ok(())
+}
+
+pub fn create_auction(
+    ctx: Context<CreateAuction>,
+    strike: u64,
+    params: AuctionParams,
+) -> Result<()> {
+    let auction = &mut ctx.accounts.auction;
+    auction.state = ctx.accounts.state.key();
+    auction.strike = strike;
+    auction.bump = *ctx.bumps.get("auction").unwrap();
+    auction.lots_for_sale = params.lots_for_sale;
+    auction.lots_sold = 0;
+    auction.start_time = params.start_time;
+    auction.end_time = params.end_time;
+    auction.start_price = params.start_price;
+    auction.floor_price = params.floor_price;
+    auction.decay = params.decay;
+
+    ctx.accounts
+        .state
+        .reserve_collateral(&mut ctx.accounts.strike_state, params.lots_for_sale)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CreateAuction<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The strike needs to exist before selling options on it.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_AUCTION_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = Auction::SPACE
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateAuction<'info> {
+    pub fn validate_accounts(&self, _strike: u64, params: &AuctionParams) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        params.validate(&self.state)?;
+
+        Ok(())
+    }
+}
+
+pub fn buy_from_auction(
+    ctx: Context<BuyFromAuction>,
+    strike: u64,
+    amount_lots: u64,
+    max_price_per_lot: u64,
+) -> Result<()> {
+    check_mint!(ctx, strike, bump);
+
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let price_per_lot: u64 = ctx.accounts.auction.price_at(now);
+    require!(
+        price_per_lot <= max_price_per_lot,
+        SOErrorCode::PriceTooHigh
+    );
+
+    // Take the premium.
+    let payment: u64 = amount_lots.checked_mul(price_per_lot).unwrap();
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.buyer_quote_account.owner.key(),
+        payment,
+    );
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.buyer_quote_account.to_account_info(),
+        ctx.accounts.project_quote_account.to_account_info(),
+        ctx.accounts.fee_quote_account.to_account_info(),
+        ctx.accounts.authority.to_account_info(),
+        &[],
+        payment,
+        fee,
+    )?;
+
+    mint_options(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.option_mint.to_account_info(),
+        ctx.accounts.buyer_so_account.to_account_info(),
+        ctx.accounts.state.key(),
+        strike,
+        bump,
+        &mut ctx.accounts.strike_state,
+        amount_lots,
+    )?;
+
+    ctx.accounts.auction.lots_sold = ctx
+        .accounts
+        .auction
+        .lots_sold
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        OptionsIssued {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            recipient: ctx.accounts.buyer_so_account.key(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct BuyFromAuction<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_AUCTION_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = auction.bump
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the options will be sent.
+    #[account(mut)]
+    pub buyer_so_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the premium is coming from.
+    #[account(mut)]
+    pub buyer_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the premium is going
+    #[account(mut)]
+    pub project_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going
+    #[account(mut)]
+    pub fee_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the premium.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> BuyFromAuction<'info> {
+    pub fn validate_accounts(
+        &self,
+        _strike: u64,
+        amount_lots: u64,
+        _max_price_per_lot: u64,
+    ) -> Result<()> {
+        let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+        require!(
+            self.auction.start_time <= now && now <= self.auction.end_time,
+            SOErrorCode::AuctionNotActive
+        );
+
+        require!(amount_lots > 0, SOErrorCode::InvalidConfig);
+        require!(
+            self.auction.lots_sold.checked_add(amount_lots).unwrap() <= self.auction.lots_for_sale,
+            SOErrorCode::NotEnoughTokens
+        );
+
+        validate_premium_account(&self.state, &self.project_quote_account)?;
+
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_quote_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        Ok(())
+    }
+}
+
+// Ends the auction early or cleans up after it. Collateral for unsold lots is
+// returned to options_available.
+pub fn close_auction(ctx: Context<CloseAuction>, strike: u64) -> Result<()> {
+    let unsold_lots: u64 = ctx
+        .accounts
+        .auction
+        .lots_for_sale
+        .checked_sub(ctx.accounts.auction.lots_sold)
+        .unwrap();
+    ctx.accounts.state.release_collateral(unsold_lots, strike);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CloseAuction<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        close = authority,
+        seeds = [SO_AUCTION_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = auction.bump
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+}
+
+impl<'info> CloseAuction<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        check_not_expired!(self.state.option_expiration);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,461 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn register_auto_exercise(
+    ctx: Context<RegisterAutoExercise>,
+    strike: u64,
+    max_lots: u64,
+) -> Result<()> {
+    let auto_exercise = &mut ctx.accounts.auto_exercise;
+    auto_exercise.state = ctx.accounts.state.key();
+    auto_exercise.strike = strike;
+    auto_exercise.owner = ctx.accounts.authority.key();
+    auto_exercise.bump = *ctx.bumps.get("auto_exercise").unwrap();
+    auto_exercise.user_so_account = ctx.accounts.user_so_account.key();
+    auto_exercise.user_quote_account = ctx.accounts.user_quote_account.key();
+    auto_exercise.user_base_account = ctx.accounts.user_base_account.key();
+
+    // Approve the registration to spend the options and the payment for them
+    // at the highest strike the schedule reaches.
+    let max_strike: u64 = ctx
+        .accounts
+        .strike_state
+        .strike_schedule
+        .iter()
+        .map(|breakpoint| breakpoint.strike)
+        .fold(strike, std::cmp::max);
+    anchor_spl::token::approve(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Approve {
+                to: ctx.accounts.user_so_account.to_account_info(),
+                delegate: ctx.accounts.auto_exercise.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        max_lots,
+    )?;
+    anchor_spl::token::approve(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Approve {
+                to: ctx.accounts.user_quote_account.to_account_info(),
+                delegate: ctx.accounts.auto_exercise.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        max_lots.checked_mul(max_strike).unwrap(),
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct RegisterAutoExercise<'info> {
+    /// Owner of the options.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [
+            SO_AUTO_EXERCISE_SEED,
+            &state.key().to_bytes(),
+            &strike.to_be_bytes(),
+            &authority.key().to_bytes()
+        ],
+        bump,
+        space = AutoExercise::SPACE
+    )]
+    pub auto_exercise: Box<Account<'info, AutoExercise>>,
+
+    #[account(
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Options that will be exercised.
+    #[account(mut,
+        token::authority = authority,
+        constraint = user_so_account.mint == Pubkey::find_program_address(
+            &[SO_MINT_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+            &crate::ID
+        ).0 @ SOErrorCode::InvalidMint
+    )]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the payment will come from.
+    #[account(mut,
+        token::authority = authority,
+        token::mint = state.quote_mint
+    )]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the base tokens will go.
+    #[account(
+        token::authority = authority,
+        token::mint = state.base_mint
+    )]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterAutoExercise<'info> {
+    pub fn validate_accounts(&self, _strike: u64, max_lots: u64) -> Result<()> {
+        check_not_expired!(self.state.option_expiration);
+
+        // Auto exercise settles against the oracle and pays the strike.
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+        require!(
+            self.state.oracle != Pubkey::default(),
+            SOErrorCode::InvalidOracle
+        );
+        require!(max_lots > 0, SOErrorCode::NothingToExercise);
+
+        // A token account has a single delegate, so approving here would
+        // replace an approval still in use, for example the registration of
+        // another strike paid from the same quote account. Each registration
+        // needs its own quote account.
+        for account in [&self.user_so_account, &self.user_quote_account] {
+            require!(
+                account.delegate.is_none() || account.delegated_amount == 0,
+                SOErrorCode::AlreadyDelegated
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub fn unregister_auto_exercise(ctx: Context<UnregisterAutoExercise>, _strike: u64) -> Result<()> {
+    // Only revoke if the registration is still the delegate, otherwise the
+    // owner already approved something else.
+    if ctx.accounts.user_so_account.delegate == Some(ctx.accounts.auto_exercise.key()).into() {
+        anchor_spl::token::revoke(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Revoke {
+                source: ctx.accounts.user_so_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ))?;
+    }
+    if ctx.accounts.user_quote_account.delegate == Some(ctx.accounts.auto_exercise.key()).into() {
+        anchor_spl::token::revoke(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Revoke {
+                source: ctx.accounts.user_quote_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct UnregisterAutoExercise<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        close = authority,
+        seeds = [
+            SO_AUTO_EXERCISE_SEED,
+            &auto_exercise.state.to_bytes(),
+            &strike.to_be_bytes(),
+            &authority.key().to_bytes()
+        ],
+        bump = auto_exercise.bump
+    )]
+    pub auto_exercise: Box<Account<'info, AutoExercise>>,
+
+    #[account(mut, address = auto_exercise.user_so_account)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+
+    #[account(mut, address = auto_exercise.user_quote_account)]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Exercises as many of the approved options as the approved payment allows.
+// Anyone can call this in the last AUTO_EXERCISE_WINDOW_SEC before expiration
+// when the base is worth more than the strike plus the fee at the oracle price.
+pub fn crank_auto_exercise(ctx: Context<CrankAutoExercise>, strike: u64) -> Result<()> {
+    // Verify the mint is correct.
+    check_mint!(ctx, strike, _bump);
+
+    let oracle_price =
+        load_oracle_price(&ctx.accounts.oracle, ctx.accounts.state.oracle_max_age_sec)?;
+    let price: u64 = price_per_lot(
+        &oracle_price,
+        ctx.accounts.state.lot_size,
+        ctx.accounts.state.base_decimals,
+        ctx.accounts.quote_mint.decimals,
+    );
+    let current_strike: u64 = ctx
+        .accounts
+        .strike_state
+        .strike_at(Clock::get().unwrap().unix_timestamp as u64);
+
+    let delegate: Pubkey = ctx.accounts.auto_exercise.key();
+    let approved_lots: u64 = if ctx.accounts.user_so_account.delegate == Some(delegate).into() {
+        std::cmp::min(
+            ctx.accounts.user_so_account.delegated_amount,
+            ctx.accounts.user_so_account.amount,
+        )
+    } else {
+        0
+    };
+    // Fails for a strike scheduled down to zero instead of dividing by it.
+    let payable_lots: u64 = if ctx.accounts.user_quote_account.delegate == Some(delegate).into() {
+        std::cmp::min(
+            ctx.accounts.user_quote_account.delegated_amount,
+            ctx.accounts.user_quote_account.amount,
+        )
+        .checked_div(current_strike)
+        .ok_or(SOErrorCode::InvalidConfig)?
+    } else {
+        0
+    };
+    let amount_lots: u64 = std::cmp::min(approved_lots, payable_lots);
+    require!(amount_lots > 0, SOErrorCode::NothingToExercise);
+
+    // A strike barely in the money would cost the holder more than the base
+    // is worth once the fee is added.
+    let payment: u64 = amount_lots.checked_mul(current_strike).unwrap();
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.auto_exercise.owner,
+        payment,
+    );
+    require!(
+        amount_lots as u128 * price as u128 > payment as u128 + fee as u128,
+        SOErrorCode::OutOfTheMoney
+    );
+
+    let auto_exercise_seeds: &[&[&[u8]]] = &[&[
+        SO_AUTO_EXERCISE_SEED,
+        &ctx.accounts.state.key().to_bytes(),
+        &strike.to_be_bytes(),
+        &ctx.accounts.auto_exercise.owner.to_bytes(),
+        &[ctx.accounts.auto_exercise.bump],
+    ]];
+
+    // Take the option tokens and burn
+    anchor_spl::token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.user_so_account.to_account_info(),
+                authority: ctx.accounts.auto_exercise.to_account_info(),
+            },
+            auto_exercise_seeds,
+        ),
+        amount_lots,
+    )?;
+
+    // Take the Quote Token payment
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_quote_account.to_account_info(),
+        ctx.accounts.project_quote_account.to_account_info(),
+        ctx.accounts.fee_quote_account.to_account_info(),
+        ctx.accounts.auto_exercise.to_account_info(),
+        auto_exercise_seeds,
+        payment,
+        fee,
+    )?;
+
+    // Transfer the base tokens
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.base_vault.to_account_info(),
+                to: ctx.accounts.user_base_account.to_account_info(),
+                authority: ctx.accounts.base_vault.to_account_info(),
+            },
+            &[&[
+                SO_VAULT_SEED,
+                &ctx.accounts.state.so_name.as_bytes(),
+                &ctx.accounts.state.base_mint.key().to_bytes(),
+                &[ctx.accounts.state.vault_bump],
+            ]],
+        ),
+        amount_lots
+            .checked_mul(ctx.accounts.state.lot_size)
+            .unwrap(),
+    )?;
+
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    // The owner is the one exercising, the caller only cranks.
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Exercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.auto_exercise.owner,
+            strike,
+            amount_lots,
+            payment,
+            fee,
+            fee_mint: ctx.accounts.state.quote_mint,
+            amount_delivered: amount_lots
+                .checked_mul(ctx.accounts.state.lot_size)
+                .unwrap(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CrankAutoExercise<'info> {
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(
+        seeds = [
+            SO_AUTO_EXERCISE_SEED,
+            &state.key().to_bytes(),
+            &strike.to_be_bytes(),
+            &auto_exercise.owner.to_bytes()
+        ],
+        bump = auto_exercise.bump
+    )]
+    pub auto_exercise: Box<Account<'info, AutoExercise>>,
+
+    /// Where the SO are coming from.
+    #[account(mut, address = auto_exercise.user_so_account)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+    /// Mint is needed to burn the options.
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// CHECK: Must be the oracle saved on the State. Parsed in the handler.
+    #[account(address = state.oracle)]
+    pub oracle: AccountInfo<'info>,
+
+    /// Needed for the number of decimals to convert the oracle price.
+    #[account(address = state.quote_mint)]
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    /// Where the payment is coming from.
+    #[account(mut, address = auto_exercise.user_quote_account)]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the payment is going
+    #[account(mut, address = state.quote_account @ SOErrorCode::IncorrectFeeAccount)]
+    pub project_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going
+    #[account(mut)]
+    pub fee_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the payment.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    /// The base token location for this SO.
+    #[account(mut,
+        seeds = [SO_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.vault_bump,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the base tokens are going.
+    #[account(mut, address = auto_exercise.user_base_account)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> CrankAutoExercise<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_quote_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        // Only in the last window before expiration, so holders still have
+        // the chance to exercise themselves.
+        check_not_expired!(self.state.option_expiration);
+        require!(
+            Clock::get().unwrap().unix_timestamp as u64
+                >= self
+                    .state
+                    .option_expiration
+                    .saturating_sub(AUTO_EXERCISE_WINDOW_SEC),
+            SOErrorCode::NotYetExercisable
+        );
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+        require!(self.strike_state.cap == 0, SOErrorCode::CashSettledOnly);
+
+        Ok(())
+    }
+}
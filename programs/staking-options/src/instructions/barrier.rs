@@ -0,0 +1,153 @@
+use anchor_spl::token::Mint;
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_barrier(
+    ctx: Context<SetBarrier>,
+    _strike: u64,
+    barrier_kind: BarrierKind,
+    barrier_price: u64,
+) -> Result<()> {
+    // Verify that the account is a live price feed.
+    load_oracle_price(
+        &ctx.accounts.barrier_oracle,
+        ctx.accounts.state.oracle_max_age_sec,
+    )?;
+
+    let strike_state = &mut ctx.accounts.strike_state;
+    strike_state.barrier_kind = barrier_kind;
+    strike_state.barrier_price = barrier_price;
+    strike_state.barrier_oracle = ctx.accounts.barrier_oracle.key();
+    strike_state.barrier_triggered = false;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct SetBarrier<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// CHECK: Parsed as a Pyth price account in the handler.
+    pub barrier_oracle: AccountInfo<'info>,
+}
+
+impl<'info> SetBarrier<'info> {
+    pub fn validate_accounts(&self, barrier_kind: BarrierKind, barrier_price: u64) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        require!(
+            !self.strike_state.terms_locked(),
+            SOErrorCode::InvalidConfig
+        );
+
+        // The max age of prices comes from set_oracle.
+        require!(
+            self.state.oracle_max_age_sec > 0,
+            SOErrorCode::InvalidOracle
+        );
+
+        require!(
+            barrier_kind == BarrierKind::None || barrier_price > 0,
+            SOErrorCode::InvalidConfig
+        );
+
+        Ok(())
+    }
+}
+
+// Records that the oracle price crossed the barrier. Anyone can call this, and
+// once triggered it stays triggered.
+pub fn trigger_barrier(ctx: Context<TriggerBarrier>, _strike: u64) -> Result<()> {
+    let oracle_price = load_oracle_price(
+        &ctx.accounts.barrier_oracle,
+        ctx.accounts.state.oracle_max_age_sec,
+    )?;
+    let price: u64 = price_per_lot(
+        &oracle_price,
+        ctx.accounts.state.lot_size,
+        ctx.accounts.state.base_decimals,
+        ctx.accounts.quote_mint.decimals,
+    );
+
+    let strike_state = &mut ctx.accounts.strike_state;
+    let reached: bool = if strike_state.barrier_kind.is_up() {
+        price >= strike_state.barrier_price
+    } else {
+        price <= strike_state.barrier_price
+    };
+    require!(reached, SOErrorCode::BarrierNotReached);
+
+    strike_state.barrier_triggered = true;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct TriggerBarrier<'info> {
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// CHECK: Must be the barrier oracle of the strike. Parsed in the handler.
+    #[account(address = strike_state.barrier_oracle)]
+    pub barrier_oracle: AccountInfo<'info>,
+
+    /// Needed for the number of decimals to convert the oracle price.
+    #[account(address = state.quote_mint)]
+    pub quote_mint: Box<Account<'info, Mint>>,
+}
+
+impl<'info> TriggerBarrier<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require!(
+            self.strike_state.barrier_kind != BarrierKind::None,
+            SOErrorCode::InvalidConfig
+        );
+        require!(
+            !self.strike_state.barrier_triggered,
+            SOErrorCode::InvalidConfig
+        );
+
+        // Only price moves during the life of the option count.
+        check_not_expired!(self.state.option_expiration);
+
+        Ok(())
+    }
+}
@@ -3,6 +3,231 @@ use anchor_spl::token::{Mint, Token, TokenAccount};
 
 pub use crate::*;
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ConfigParams {
+    // Seconds since unix epoch for options to expire.
+    pub option_expiration: u64,
+    // Seconds since unix epoch for subscription period end.
+    pub subscription_period_end: u64,
+    // Number of atoms to back the options, base for calls and quote for puts.
+    pub num_tokens: u64,
+    // Number of atoms of the base token to be traded per lot.
+    pub lot_size: u64,
+    pub so_name: String,
+    // Creates the quote vault needed for reversible strikes.
+    pub reversible: bool,
+    // Seconds since unix epoch before which options cannot be exercised. Zero
+    // for no restriction.
+    pub exercise_start: u64,
+    // Number of decimals for the option mints.
+    pub option_decimals: u8,
+    // Puts escrow quote tokens in the put vault instead of base in the base
+    // vault, and save the base_account to receive base delivered on exercise.
+    pub option_type: OptionType,
+}
+
+// configV4 takes all the options in a single struct so adding a new option
+// does not require a new instruction.
+pub fn config_v4(ctx: Context<ConfigV4>, params: ConfigParams) -> Result<()> {
+    // Verify the SO name is a reasonable length.
+    require!(params.so_name.len() < 32, SOErrorCode::InvalidName);
+
+    // Fill out the State
+    ctx.accounts.state.so_name = params.so_name;
+    ctx.accounts.state.authority = ctx.accounts.so_authority.key();
+
+    let optional_issue_authority = &mut ctx.accounts.issue_authority;
+    if let Some(unwrapped_issue_authority) = optional_issue_authority {
+        ctx.accounts.state.issue_authority = unwrapped_issue_authority.key();
+    }
+    ctx.accounts.state.options_available = params.num_tokens;
+    ctx.accounts.state.option_expiration = params.option_expiration;
+    ctx.accounts.state.subscription_period_end = params.subscription_period_end;
+    ctx.accounts.state.exercise_start = params.exercise_start;
+    ctx.accounts.state.base_decimals = ctx.accounts.base_mint.decimals;
+    ctx.accounts.state.option_decimals = params.option_decimals;
+    ctx.accounts.state.base_mint = ctx.accounts.base_mint.key();
+    ctx.accounts.state.quote_mint = ctx.accounts.quote_mint.key();
+    ctx.accounts.state.lot_size = params.lot_size;
+    ctx.accounts.state.option_type = params.option_type;
+    // Do not need to initialize strikes as empty vector.
+
+    // Discounted deal types are set afterwards by the fee schedule authority.
+    ctx.accounts.state.deal_type = DealType::Standard;
+    ctx.accounts.state.version = STATE_VERSION;
+
+    ctx.accounts.state.state_bump = *ctx.bumps.get("state").unwrap();
+    if params.reversible {
+        ctx.accounts.state.quote_vault_bump = *ctx.bumps.get("quote_vault").unwrap();
+    }
+
+    // Take tokens that will back the options.
+    let transfer = match params.option_type {
+        OptionType::Call => {
+            ctx.accounts.state.quote_account = ctx.accounts.quote_account.key();
+            ctx.accounts.state.vault_bump = *ctx.bumps.get("base_vault").unwrap();
+            token::Transfer {
+                from: ctx.accounts.base_account.to_account_info(),
+                to: ctx.accounts.base_vault.as_ref().unwrap().to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            }
+        }
+        OptionType::Put => {
+            // Puts receive base on exercise, so that is the account saved.
+            ctx.accounts.state.quote_account = ctx.accounts.base_account.key();
+            ctx.accounts.state.put_vault_bump = *ctx.bumps.get("put_vault").unwrap();
+            token::Transfer {
+                from: ctx.accounts.quote_account.to_account_info(),
+                to: ctx.accounts.put_vault.as_ref().unwrap().to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            }
+        }
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer),
+        params.num_tokens,
+    )?;
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        SoConfigured::from_state(&ctx.accounts.state),
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(params: ConfigParams)]
+pub struct ConfigV4<'info> {
+    /// Does not have to match the authority for the SO State, but it can.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The authority that will be required init strike and withdrawing.
+    /// CHECK: Only used for comparing signers. Will be used in later transactions.
+    pub so_authority: AccountInfo<'info>,
+
+    /// An authority that can be used for issuing tokens. Should be a PDA.
+    /// CHECK: Only used for comparing signers. Will be used in later transactions.
+    pub issue_authority: Option<AccountInfo<'info>>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SO_CONFIG_SEED, params.so_name.as_bytes(), &base_mint.key().to_bytes()],
+        bump,
+        space = State::SPACE
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Where the base tokens are going to be held. Only for calls.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SO_VAULT_SEED, params.so_name.as_bytes(), &base_mint.key().to_bytes()],
+        bump,
+        token::mint = base_mint,
+        token::authority = base_vault)]
+    pub base_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Where the quote tokens are going to be held. Only for reversible.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SO_REVERSE_VAULT_SEED, params.so_name.as_bytes(), &base_mint.key().to_bytes()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = quote_vault)]
+    pub quote_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Where the quote tokens backing the puts are going to be held. Only for
+    /// puts.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SO_PUT_VAULT_SEED, params.so_name.as_bytes(), &base_mint.key().to_bytes()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = put_vault)]
+    pub put_vault: Option<Box<Account<'info, TokenAccount>>>,
+
+    /// Where the tokens are coming from for calls. Saved to receive the base
+    /// delivered on exercise for puts.
+    #[account(mut)]
+    pub base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Saved to receive payments for calls. Where the tokens are coming from
+    /// for puts. TokenAccount instead of AccountInfo in order to get the
+    /// anchor type checking.
+    #[account(mut)]
+    pub quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Mint of base tokens.
+    pub base_mint: Box<Account<'info, Mint>>,
+    /// Mint of quote tokens.
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> ConfigV4<'info> {
+    pub fn validate_accounts(&self, params: &ConfigParams) -> Result<()> {
+        // Verify the type of token matches input
+        require_keys_eq!(self.base_mint.key(), self.base_account.mint.key());
+        require_keys_eq!(self.quote_mint.key(), self.quote_account.mint.key());
+
+        // num_tokens is verified by the token program doing the transfer.
+
+        // Make sure it is not already expired.
+        check_not_expired!(params.option_expiration);
+        check_not_expired!(params.subscription_period_end);
+
+        require!(
+            params.subscription_period_end <= params.option_expiration,
+            SOErrorCode::InvalidExpiration
+        );
+        require!(
+            params.exercise_start <= params.option_expiration,
+            SOErrorCode::InvalidExpiration
+        );
+
+        // Lots are counted in atoms of the option token, so each needs base
+        // behind it. Option tokens are no more divisible than the base.
+        require!(params.lot_size > 0, SOErrorCode::InvalidConfig);
+        require!(
+            params.option_decimals <= self.base_mint.decimals,
+            SOErrorCode::InvalidConfig
+        );
+
+        // The quote vault is only created for reversible SOs.
+        require!(
+            params.reversible == self.quote_vault.is_some(),
+            SOErrorCode::InvalidConfig
+        );
+
+        // Only the vault that backs the options is created.
+        let put: bool = params.option_type == OptionType::Put;
+        require!(
+            self.base_vault.is_none() == put && self.put_vault.is_some() == put,
+            SOErrorCode::InvalidConfig
+        );
+
+        // Puts cannot be reversed.
+        require!(!(put && params.reversible), SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
+
 // configV3 is same as v2 except it includes the reverse.
 pub fn config_v3(
     ctx: Context<ConfigV3>,
@@ -33,6 +258,10 @@ pub fn config_v3(
     ctx.accounts.state.lot_size = lot_size;
     // Do not need to initialize strikes as empty vector.
 
+    // Discounted deal types are set afterwards by the fee schedule authority.
+    ctx.accounts.state.deal_type = DealType::Standard;
+    ctx.accounts.state.version = STATE_VERSION;
+
     ctx.accounts.state.state_bump = *ctx.bumps.get("state").unwrap();
     ctx.accounts.state.vault_bump = *ctx.bumps.get("base_vault").unwrap();
 
@@ -49,6 +278,12 @@ pub fn config_v3(
     );
     token::transfer(cpi_ctx, num_tokens)?;
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        SoConfigured::from_state(&ctx.accounts.state),
+    )?;
+
     Ok(())
 }
 
@@ -73,23 +308,7 @@ pub struct ConfigV3<'info> {
         payer = authority,
         seeds = [SO_CONFIG_SEED, so_name.as_bytes(), &base_mint.key().to_bytes()],
         bump,
-        space =
-          8 +       // discriminator
-          64 +      // so_name
-          32 +      // authority
-          8 +       // options_available
-          8 +       // option_expiration
-          8 +       // subscription_period_end
-          8 +       // decimals
-          32 +      // base_mint 
-          32 +      // quote_mint 
-          32 +      // quote_account 
-          8 +       // lot size
-          1 + 1 +   // bumps
-          8 +       // strikes overhead
-          8 * 100 + // strikes
-          32 +      // issue_authority
-          68        // unused bytes for future upgrades
+        space = State::SPACE
     )]
     pub state: Box<Account<'info, State>>,
 
@@ -130,6 +349,11 @@ pub struct ConfigV3<'info> {
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 pub fn config_v2(
@@ -165,6 +389,10 @@ pub fn config_v2(
     ctx.accounts.state.lot_size = lot_size;
     // Do not need to initialize strikes as empty vector.
 
+    // Discounted deal types are set afterwards by the fee schedule authority.
+    ctx.accounts.state.deal_type = DealType::Standard;
+    ctx.accounts.state.version = STATE_VERSION;
+
     ctx.accounts.state.state_bump = *ctx.bumps.get("state").unwrap();
     ctx.accounts.state.vault_bump = *ctx.bumps.get("base_vault").unwrap();
 
@@ -179,6 +407,12 @@ pub fn config_v2(
     );
     token::transfer(cpi_ctx, num_tokens)?;
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        SoConfigured::from_state(&ctx.accounts.state),
+    )?;
+
     Ok(())
 }
 
@@ -231,23 +465,7 @@ pub struct ConfigV2<'info> {
         payer = authority,
         seeds = [SO_CONFIG_SEED, so_name.as_bytes(), &base_mint.key().to_bytes()],
         bump,
-        space =
-          8 +       // discriminator
-          64 +      // so_name
-          32 +      // authority
-          8 +       // options_available
-          8 +       // option_expiration
-          8 +       // subscription_period_end
-          8 +       // decimals
-          32 +      // base_mint 
-          32 +      // quote_mint 
-          32 +      // quote_account 
-          8 +       // lot size
-          1 + 1 +   // bumps
-          8 +       // strikes overhead
-          8 * 100 + // strikes
-          32 +      // issue_authority
-          68        // unused bytes for future upgrades
+        space = State::SPACE
     )]
     pub state: Box<Account<'info, State>>,
 
@@ -277,6 +495,11 @@ pub struct ConfigV2<'info> {
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> ConfigV2<'info> {
@@ -337,6 +560,10 @@ pub fn config(
     ctx.accounts.state.lot_size = lot_size;
     // Do not need to initialize strikes as empty vector.
 
+    // Discounted deal types are set afterwards by the fee schedule authority.
+    ctx.accounts.state.deal_type = DealType::Standard;
+    ctx.accounts.state.version = STATE_VERSION;
+
     ctx.accounts.state.state_bump = *ctx.bumps.get("state").unwrap();
     ctx.accounts.state.vault_bump = *ctx.bumps.get("base_vault").unwrap();
 
@@ -351,6 +578,12 @@ pub fn config(
     );
     token::transfer(cpi_ctx, num_tokens)?;
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        SoConfigured::from_state(&ctx.accounts.state),
+    )?;
+
     Ok(())
 }
 
@@ -371,23 +604,7 @@ pub struct Config<'info> {
         payer = authority,
         seeds = [SO_CONFIG_SEED, so_name.as_bytes(), &base_mint.key().to_bytes()],
         bump,
-        space =
-          8 +       // discriminator
-          64 +      // so_name
-          32 +      // authority
-          8 +       // options_available
-          8 +       // option_expiration
-          8 +       // subscription_period_end
-          8 +       // decimals
-          32 +      // base_mint 
-          32 +      // quote_mint 
-          32 +      // quote_account 
-          8 +       // lot size
-          1 + 1 +   // bumps
-          8 +       // strikes overhead
-          8 * 100 + // strikes
-          32 +      // issue_authority
-          68        // unused bytes for future upgrades
+        space = State::SPACE
     )]
     pub state: Box<Account<'info, State>>,
 
@@ -417,6 +634,11 @@ pub struct Config<'info> {
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> Config<'info> {
@@ -6,8 +6,6 @@ pub fn exercise(ctx: Context<Exercise>, amount_lots: u64, strike: u64) -> Result
     // Verify the mint is correct.
     check_mint!(ctx, strike, bump);
 
-    // TODO: Store all of the strikes on the state object and their bumps as well as a mapping of token to strike
-
     // Take the option tokens and burn
     anchor_spl::token::burn(
         CpiContext::new_with_signer(
@@ -27,52 +25,30 @@ pub fn exercise(ctx: Context<Exercise>, amount_lots: u64, strike: u64) -> Result
         amount_lots,
     )?;
 
-    // Take the Quote Token payment
-    let payment: u64 = amount_lots.checked_mul(strike).unwrap();
+    // Take the Quote Token payment at the strike currently in effect.
+    let current_strike: u64 = ctx
+        .accounts
+        .strike_state
+        .strike_at(Clock::get().unwrap().unix_timestamp as u64);
+    let payment: u64 = amount_lots.checked_mul(current_strike).unwrap();
 
-    // Charge fee when it is not Dual DAO or Dual Labs RM is exercising.
-    if ctx.accounts.user_quote_account.owner.key().to_string()
-        != "7Z36Efbt7a4nLiV7s5bY7J2e4TJ6V9JEKGccsy2od2bE"
-        && ctx.accounts.user_quote_account.owner.key().to_string()
-            != "CkcJx7Uwgxck5zm3DqUp2N1ikkkoPn2wA8zf7oS4tFSZ"
-    {
-        // 3.5% fee.
-        let fee: u64 = payment.checked_mul(35).unwrap().checked_div(1_000).unwrap();
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.user_quote_account.to_account_info(),
-                    to: ctx.accounts.project_quote_account.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info().clone(),
-                },
-            ),
-            payment.checked_sub(fee).unwrap(),
-        )?;
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.user_quote_account.to_account_info(),
-                    to: ctx.accounts.fee_quote_account.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info().clone(),
-                },
-            ),
-            fee,
-        )?;
-    } else {
-        anchor_spl::token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                anchor_spl::token::Transfer {
-                    from: ctx.accounts.user_quote_account.to_account_info(),
-                    to: ctx.accounts.project_quote_account.to_account_info(),
-                    authority: ctx.accounts.authority.to_account_info().clone(),
-                },
-            ),
-            payment,
-        )?;
-    }
+    // No fee when the Dual DAO or Dual Labs RM is exercising.
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.user_quote_account.owner.key(),
+        payment,
+    );
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_quote_account.to_account_info(),
+        ctx.accounts.project_quote_account.to_account_info(),
+        ctx.accounts.fee_quote_account.to_account_info(),
+        ctx.accounts.authority.to_account_info(),
+        &[],
+        payment,
+        fee,
+    )?;
 
     // Transfer the base tokens
     anchor_spl::token::transfer(
@@ -95,6 +71,30 @@ pub fn exercise(ctx: Context<Exercise>, amount_lots: u64, strike: u64) -> Result
             .unwrap(),
     )?;
 
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Exercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment,
+            fee,
+            fee_mint: ctx.accounts.state.quote_mint,
+            amount_delivered: amount_lots
+                .checked_mul(ctx.accounts.state.lot_size)
+                .unwrap(),
+        },
+    )?;
+
     Ok(())
 }
 
@@ -121,6 +121,13 @@ pub struct Exercise<'info> {
     #[account(mut)]
     pub option_mint: Box<Account<'info, Mint>>,
 
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     /// Where the payment is coming from.
     #[account(mut)]
     pub user_quote_account: Box<Account<'info, TokenAccount>>,
@@ -133,6 +140,13 @@ pub struct Exercise<'info> {
     #[account(mut)]
     pub fee_quote_account: Box<Account<'info, TokenAccount>>,
 
+    /// Determines the fee charged on the payment.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
     /// The base token location for this SO.
     #[account(mut,
         seeds = [SO_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
@@ -145,6 +159,11 @@ pub struct Exercise<'info> {
     pub user_base_account: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> Exercise<'info> {
@@ -165,6 +184,20 @@ impl<'info> Exercise<'info> {
 
         // Verify expiration
         check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        // Puts are exercised with exercise_put.
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+
+        // Capped strikes pay out at most the cap, which needs the oracle.
+        require!(self.strike_state.cap == 0, SOErrorCode::CashSettledOnly);
 
         Ok(())
     }
@@ -255,6 +288,28 @@ pub fn exercise_reversible(
             .unwrap(),
     )?;
 
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        ReversibleExercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment,
+            amount_delivered: amount_lots
+                .checked_mul(ctx.accounts.state.lot_size)
+                .unwrap(),
+        },
+    )?;
+
     Ok(())
 }
 
@@ -281,6 +336,13 @@ pub struct ExerciseReversible<'info> {
     #[account(mut)]
     pub option_mint: Box<Account<'info, Mint>>,
 
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     #[account(mut)]
     pub user_reverse_so_account: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
@@ -310,12 +372,31 @@ pub struct ExerciseReversible<'info> {
     pub user_base_account: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> ExerciseReversible<'info> {
     pub fn validate_accounts(&self, _amount: u64, _strike: u64) -> Result<()> {
         // Verify expiration
         check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        // Puts cannot be reversed.
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+
+        // Capped strikes pay out at most the cap, which needs the oracle.
+        require!(self.strike_state.cap == 0, SOErrorCode::CashSettledOnly);
 
         Ok(())
     }
@@ -403,6 +484,28 @@ pub fn reverse_exercise(
             .unwrap(),
     )?;
 
+    ctx.accounts.strike_state.lots_reversed = ctx
+        .accounts
+        .strike_state
+        .lots_reversed
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        ReverseExercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment_returned: payment,
+            base_returned: amount_lots
+                .checked_mul(ctx.accounts.state.lot_size)
+                .unwrap(),
+        },
+    )?;
+
     Ok(())
 }
 
@@ -429,6 +532,13 @@ pub struct ReverseExercise<'info> {
     #[account(mut)]
     pub option_mint: Box<Account<'info, Mint>>,
 
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     #[account(mut)]
     pub user_reverse_so_account: Box<Account<'info, TokenAccount>>,
     #[account(mut)]
@@ -458,12 +568,665 @@ pub struct ReverseExercise<'info> {
     pub user_base_account: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> ReverseExercise<'info> {
     pub fn validate_accounts(&self, _amount: u64, _strike: u64) -> Result<()> {
         // Verify expiration
         check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+
+        Ok(())
+    }
+}
+
+pub fn exercise_put(ctx: Context<ExercisePut>, amount_lots: u64, strike: u64) -> Result<()> {
+    // Verify the mint is correct.
+    check_mint!(ctx, strike, bump);
+
+    // Take the option tokens and burn
+    anchor_spl::token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.user_so_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+            &[&[
+                SO_MINT_SEED,
+                &ctx.accounts.state.key().to_bytes(),
+                &strike.to_be_bytes(),
+                &[bump],
+            ]],
+        ),
+        amount_lots,
+    )?;
+
+    // Take the base tokens from the holder
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.user_base_account.to_account_info(),
+                to: ctx.accounts.project_base_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount_lots
+            .checked_mul(ctx.accounts.state.lot_size)
+            .unwrap(),
+    )?;
+
+    // Pay the strike from the put vault. The fee comes out of the payment.
+    let payment: u64 = amount_lots.checked_mul(strike).unwrap();
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.user_quote_account.owner.key(),
+        payment,
+    );
+    let put_vault_seeds: &[&[&[u8]]] = &[&[
+        SO_PUT_VAULT_SEED,
+        &ctx.accounts.state.so_name.as_bytes(),
+        &ctx.accounts.state.base_mint.key().to_bytes(),
+        &[ctx.accounts.state.put_vault_bump],
+    ]];
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.put_vault.to_account_info(),
+        ctx.accounts.user_quote_account.to_account_info(),
+        ctx.accounts.fee_quote_account.to_account_info(),
+        ctx.accounts.put_vault.to_account_info(),
+        put_vault_seeds,
+        payment,
+        fee,
+    )?;
+
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Exercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment,
+            fee,
+            fee_mint: ctx.accounts.state.quote_mint,
+            amount_delivered: payment.checked_sub(fee).unwrap(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, strike: u64)]
+pub struct ExercisePut<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Where the SO are coming from.
+    #[account(mut)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+    /// Mint is needed to burn the options.
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the base tokens are coming from.
+    #[account(mut)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the base tokens are going.
+    #[account(mut)]
+    pub project_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// The quote tokens backing the puts.
+    #[account(mut,
+        seeds = [SO_PUT_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.put_vault_bump,
+    )]
+    pub put_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the payment is going.
+    #[account(mut)]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going
+    #[account(mut)]
+    pub fee_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the payment.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> ExercisePut<'info> {
+    pub fn validate_accounts(&self, _amount: u64, _strike: u64) -> Result<()> {
+        require!(
+            self.state.option_type == OptionType::Put,
+            SOErrorCode::WrongOptionType
+        );
+
+        // Verify the address of the account receiving base. Because this
+        // account matches, the token type will also be verified by the token
+        // program.
+        require_keys_eq!(
+            self.state.quote_account.key(),
+            self.project_base_account.key(),
+            SOErrorCode::IncorrectFeeAccount
+        );
+
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_quote_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        // Verify expiration
+        check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        Ok(())
+    }
+}
+
+// Cash settled exercise. Instead of paying the strike, the holder receives the
+// in the money value of the options in base tokens, priced with the oracle.
+// The rest of the base backing the options becomes available again.
+pub fn exercise_cash(ctx: Context<ExerciseCash>, amount_lots: u64, strike: u64) -> Result<()> {
+    // Verify the mint is correct.
+    check_mint!(ctx, strike, bump);
+
+    let oracle_price =
+        load_oracle_price(&ctx.accounts.oracle, ctx.accounts.state.oracle_max_age_sec)?;
+    let price: u64 = price_per_lot(
+        &oracle_price,
+        ctx.accounts.state.lot_size,
+        ctx.accounts.state.base_decimals,
+        ctx.accounts.quote_mint.decimals,
+    );
+    let current_strike: u64 = ctx
+        .accounts
+        .strike_state
+        .strike_at(Clock::get().unwrap().unix_timestamp as u64);
+    // The payoff stops growing above the cap.
+    let settlement_price: u64 = ctx.accounts.strike_state.capped_price(price);
+    require!(
+        settlement_price > current_strike,
+        SOErrorCode::OutOfTheMoney
+    );
+
+    // Take the option tokens and burn
+    anchor_spl::token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.user_so_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+            &[&[
+                SO_MINT_SEED,
+                &ctx.accounts.state.key().to_bytes(),
+                &strike.to_be_bytes(),
+                &[bump],
+            ]],
+        ),
+        amount_lots,
+    )?;
+
+    // Base atoms worth (settlement price - strike) per lot at the oracle
+    // price.
+    let base_backing: u64 = amount_lots
+        .checked_mul(ctx.accounts.state.lot_size)
+        .unwrap();
+    let intrinsic_base: u64 = (base_backing as u128)
+        .checked_mul(settlement_price.checked_sub(current_strike).unwrap() as u128)
+        .unwrap()
+        .checked_div(price as u128)
+        .unwrap() as u64;
+
+    // The fee is charged on the value received and paid in base.
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.user_base_account.owner.key(),
+        intrinsic_base,
+    );
+    let base_vault_seeds: &[&[&[u8]]] = &[&[
+        SO_VAULT_SEED,
+        &ctx.accounts.state.so_name.as_bytes(),
+        &ctx.accounts.state.base_mint.key().to_bytes(),
+        &[ctx.accounts.state.vault_bump],
+    ]];
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.base_vault.to_account_info(),
+        ctx.accounts.user_base_account.to_account_info(),
+        ctx.accounts.fee_base_account.to_account_info(),
+        ctx.accounts.base_vault.to_account_info(),
+        base_vault_seeds,
+        intrinsic_base,
+        fee,
+    )?;
+
+    // Base not paid out stays in the vault for the project.
+    ctx.accounts.state.options_available = ctx
+        .accounts
+        .state
+        .options_available
+        .checked_add(base_backing.checked_sub(intrinsic_base).unwrap())
+        .unwrap();
+
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Exercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment: 0,
+            fee,
+            fee_mint: ctx.accounts.state.base_mint,
+            amount_delivered: intrinsic_base.checked_sub(fee).unwrap(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, strike: u64)]
+pub struct ExerciseCash<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Where the SO are coming from.
+    #[account(mut)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+    /// Mint is needed to burn the options.
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// CHECK: Must be the oracle saved on the State. Parsed in the handler.
+    #[account(address = state.oracle)]
+    pub oracle: AccountInfo<'info>,
+
+    /// Needed for the number of decimals to convert the oracle price.
+    #[account(address = state.quote_mint)]
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    /// The base token location for this SO.
+    #[account(mut,
+        seeds = [SO_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.vault_bump,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the base tokens are going.
+    #[account(mut)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going. Paid in base.
+    #[account(mut)]
+    pub fee_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the payout.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> ExerciseCash<'info> {
+    pub fn validate_accounts(&self, _amount: u64, _strike: u64) -> Result<()> {
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+
+        // Cash settlement is only possible once the authority set an oracle.
+        require!(
+            self.state.oracle != Pubkey::default(),
+            SOErrorCode::InvalidOracle
+        );
+
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_base_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        // Verify expiration
+        check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        Ok(())
+    }
+}
+
+// Converts quote atoms to base atoms at a price in quote atoms per lot.
+fn quote_to_base(quote_atoms: u64, lot_size: u64, price: u64, round_up: bool) -> u64 {
+    let numerator: u128 = (quote_atoms as u128).checked_mul(lot_size as u128).unwrap();
+    let rounding: u128 = if round_up { price as u128 - 1 } else { 0 };
+    numerator
+        .checked_add(rounding)
+        .unwrap()
+        .checked_div(price as u128)
+        .unwrap() as u64
+}
+
+// Net exercise. The strike and fee are paid in base tokens valued at the
+// oracle price less the haircut, and the holder receives the rest of the base.
+pub fn net_exercise(ctx: Context<NetExercise>, amount_lots: u64, strike: u64) -> Result<()> {
+    // Verify the mint is correct.
+    check_mint!(ctx, strike, bump);
+
+    let oracle_price =
+        load_oracle_price(&ctx.accounts.oracle, ctx.accounts.state.oracle_max_age_sec)?;
+    let price: u64 = price_per_lot(
+        &oracle_price,
+        ctx.accounts.state.lot_size,
+        ctx.accounts.state.base_decimals,
+        ctx.accounts.quote_mint.decimals,
+    );
+    let valued_price: u64 = ((price as u128)
+        .checked_mul(
+            MAX_FEE_BPS
+                .checked_sub(ctx.accounts.state.net_exercise_haircut_bps as u64)
+                .unwrap() as u128,
+        )
+        .unwrap()
+        .checked_div(MAX_FEE_BPS as u128)
+        .unwrap()) as u64;
+    let current_strike: u64 = ctx
+        .accounts
+        .strike_state
+        .strike_at(Clock::get().unwrap().unix_timestamp as u64);
+    require!(valued_price > current_strike, SOErrorCode::OutOfTheMoney);
+
+    // Take the option tokens and burn
+    anchor_spl::token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.user_so_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+            &[&[
+                SO_MINT_SEED,
+                &ctx.accounts.state.key().to_bytes(),
+                &strike.to_be_bytes(),
+                &[bump],
+            ]],
+        ),
+        amount_lots,
+    )?;
+
+    // Same payment and fee as a regular exercise, converted to base. The
+    // project portion rounds up so the project is never underpaid.
+    let payment: u64 = amount_lots.checked_mul(current_strike).unwrap();
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.user_base_account.owner.key(),
+        payment,
+    );
+    let lot_size: u64 = ctx.accounts.state.lot_size;
+    let project_base: u64 = quote_to_base(
+        payment.checked_sub(fee).unwrap(),
+        lot_size,
+        valued_price,
+        true,
+    );
+    let fee_base: u64 = quote_to_base(fee, lot_size, valued_price, false);
+    let user_base: u64 = amount_lots
+        .checked_mul(lot_size)
+        .unwrap()
+        .checked_sub(project_base)
+        .unwrap()
+        .checked_sub(fee_base)
+        .unwrap();
+
+    let base_vault_seeds: &[&[&[u8]]] = &[&[
+        SO_VAULT_SEED,
+        &ctx.accounts.state.so_name.as_bytes(),
+        &ctx.accounts.state.base_mint.key().to_bytes(),
+        &[ctx.accounts.state.vault_bump],
+    ]];
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.base_vault.to_account_info(),
+        ctx.accounts.project_base_account.to_account_info(),
+        ctx.accounts.fee_base_account.to_account_info(),
+        ctx.accounts.base_vault.to_account_info(),
+        base_vault_seeds,
+        project_base.checked_add(fee_base).unwrap(),
+        fee_base,
+    )?;
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.base_vault.to_account_info(),
+                to: ctx.accounts.user_base_account.to_account_info(),
+                authority: ctx.accounts.base_vault.to_account_info(),
+            },
+            base_vault_seeds,
+        ),
+        user_base,
+    )?;
+
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Exercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment,
+            fee: fee_base,
+            fee_mint: ctx.accounts.state.base_mint,
+            amount_delivered: user_base,
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, strike: u64)]
+pub struct NetExercise<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Where the SO are coming from.
+    #[account(mut)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+    /// Mint is needed to burn the options.
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// CHECK: Must be the oracle saved on the State. Parsed in the handler.
+    #[account(address = state.oracle)]
+    pub oracle: AccountInfo<'info>,
+
+    /// Needed for the number of decimals to convert the oracle price.
+    #[account(address = state.quote_mint)]
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    /// The base token location for this SO.
+    #[account(mut,
+        seeds = [SO_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.vault_bump,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the remaining base tokens are going.
+    #[account(mut)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the payment is going. Paid in base.
+    #[account(mut,
+        token::authority = state.authority,
+        token::mint = state.base_mint
+    )]
+    pub project_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going. Paid in base.
+    #[account(mut)]
+    pub fee_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the payment.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> NetExercise<'info> {
+    pub fn validate_accounts(&self, _amount: u64, _strike: u64) -> Result<()> {
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+        require!(
+            self.state.oracle != Pubkey::default(),
+            SOErrorCode::InvalidOracle
+        );
+
+        // Capped strikes are settled with exercise_cash.
+        require!(self.strike_state.cap == 0, SOErrorCode::CashSettledOnly);
+
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_base_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        // Verify expiration
+        check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
 
         Ok(())
     }
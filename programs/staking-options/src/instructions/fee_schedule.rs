@@ -0,0 +1,167 @@
+pub use crate::common::*;
+pub use crate::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct FeeScheduleParams {
+    pub default_fee_bps: u64,
+    pub mm_fee_bps: u64,
+    pub otc_fee_bps: u64,
+    pub mints: Vec<CategorizedMint>,
+    pub pair_fees: Vec<PairFee>,
+}
+
+impl FeeScheduleParams {
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.default_fee_bps <= MAX_FEE_BPS
+                && self.mm_fee_bps <= MAX_FEE_BPS
+                && self.otc_fee_bps <= MAX_FEE_BPS,
+            SOErrorCode::InvalidFeeSchedule
+        );
+        require!(
+            self.mints.len() <= MAX_FEE_SCHEDULE_MINTS,
+            SOErrorCode::InvalidFeeSchedule
+        );
+        require!(
+            self.pair_fees.len() <= MAX_FEE_SCHEDULE_PAIRS,
+            SOErrorCode::InvalidFeeSchedule
+        );
+        for pair_fee in self.pair_fees.iter() {
+            require!(
+                pair_fee.fee_bps <= MAX_FEE_BPS,
+                SOErrorCode::InvalidFeeSchedule
+            );
+        }
+
+        // A mint can only be in one category.
+        for (i, categorized_mint) in self.mints.iter().enumerate() {
+            require!(
+                !self.mints[i + 1..]
+                    .iter()
+                    .any(|other| other.mint == categorized_mint.mint),
+                SOErrorCode::InvalidFeeSchedule
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn apply_params(fee_schedule: &mut FeeSchedule, params: FeeScheduleParams) {
+    fee_schedule.default_fee_bps = params.default_fee_bps;
+    fee_schedule.mm_fee_bps = params.mm_fee_bps;
+    fee_schedule.otc_fee_bps = params.otc_fee_bps;
+    fee_schedule.mints = params.mints;
+    fee_schedule.pair_fees = params.pair_fees;
+}
+
+pub fn init_fee_schedule(ctx: Context<InitFeeSchedule>, params: FeeScheduleParams) -> Result<()> {
+    ctx.accounts.fee_schedule.authority = ctx.accounts.authority.key();
+    ctx.accounts.fee_schedule.bump = *ctx.bumps.get("fee_schedule").unwrap();
+    apply_params(&mut ctx.accounts.fee_schedule, params);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitFeeSchedule<'info> {
+    /// Must be the DUAL DAO.
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// There is only one fee schedule for the program.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [FEE_SCHEDULE_SEED],
+        bump,
+        space =
+          8 +                                     // discriminator
+          32 +                                    // authority
+          1 +                                     // bump
+          8 +                                     // default_fee_bps
+          8 +                                     // mm_fee_bps
+          8 +                                     // otc_fee_bps
+          4 + MAX_FEE_SCHEDULE_MINTS * (32 + 1) + // mints
+          4 + MAX_FEE_SCHEDULE_PAIRS * (1 + 1 + 8)  // pair_fees
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitFeeSchedule<'info> {
+    pub fn validate_accounts(&self, params: &FeeScheduleParams) -> Result<()> {
+        // Only the DUAL DAO can decide fees.
+        require_eq!(
+            self.authority.key().to_string(),
+            DUAL_DAO_ADDRESS,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        params.validate()?;
+
+        Ok(())
+    }
+}
+
+pub fn update_fee_schedule(
+    ctx: Context<UpdateFeeSchedule>,
+    params: FeeScheduleParams,
+) -> Result<()> {
+    apply_params(&mut ctx.accounts.fee_schedule, params);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeSchedule<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+}
+
+impl<'info> UpdateFeeSchedule<'info> {
+    pub fn validate_accounts(&self, params: &FeeScheduleParams) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.fee_schedule.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        params.validate()?;
+
+        Ok(())
+    }
+}
+
+// Returns the fee in bps that would be charged for an SO with these mints and
+// deal type. Does not modify anything, so it can be simulated.
+pub fn read_fee_bps(
+    ctx: Context<ReadFeeBps>,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    deal_type: DealType,
+) -> Result<u64> {
+    Ok(get_fee_bps(
+        &ctx.accounts.fee_schedule,
+        base_mint,
+        quote_mint,
+        deal_type,
+    ))
+}
+
+#[derive(Accounts)]
+pub struct ReadFeeBps<'info> {
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+}
@@ -0,0 +1,332 @@
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn add_swap_program(ctx: Context<AddSwapProgram>, program: Pubkey) -> Result<()> {
+    ctx.accounts.swap_whitelist_entry.program = program;
+    ctx.accounts.swap_whitelist_entry.bump = *ctx.bumps.get("swap_whitelist_entry").unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(program: Pubkey)]
+pub struct AddSwapProgram<'info> {
+    /// Must be the DUAL DAO.
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SWAP_WHITELIST_SEED, &program.to_bytes()],
+        bump,
+        space = SwapWhitelistEntry::SPACE
+    )]
+    pub swap_whitelist_entry: Box<Account<'info, SwapWhitelistEntry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddSwapProgram<'info> {
+    pub fn validate_accounts(&self, program: Pubkey) -> Result<()> {
+        // Only the DUAL DAO decides which programs are trusted.
+        require_eq!(
+            self.authority.key().to_string(),
+            DUAL_DAO_ADDRESS,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Flash exercise cannot call back into this program.
+        require_keys_neq!(program, crate::ID, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
+
+pub fn remove_swap_program(_ctx: Context<RemoveSwapProgram>) -> Result<()> {
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct RemoveSwapProgram<'info> {
+    /// Must be the DUAL DAO.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        close = authority,
+        seeds = [SWAP_WHITELIST_SEED, &swap_whitelist_entry.program.to_bytes()],
+        bump = swap_whitelist_entry.bump
+    )]
+    pub swap_whitelist_entry: Box<Account<'info, SwapWhitelistEntry>>,
+}
+
+impl<'info> RemoveSwapProgram<'info> {
+    pub fn validate_accounts(&self) -> Result<()> {
+        require_eq!(
+            self.authority.key().to_string(),
+            DUAL_DAO_ADDRESS,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        Ok(())
+    }
+}
+
+// Exercise where the base tokens are delivered before the payment. After the
+// base is sent, the swap program is called with the remaining accounts and
+// swap_data, which should leave enough quote in user_quote_account to pay the
+// strike. The payment is then taken as in exercise, and the whole transaction
+// fails if it did not arrive.
+pub fn flash_exercise<'info>(
+    ctx: Context<'_, '_, '_, 'info, FlashExercise<'info>>,
+    amount_lots: u64,
+    strike: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    // Verify the mint is correct.
+    check_mint!(ctx, strike, bump);
+
+    let project_quote_before: u64 = ctx.accounts.project_quote_account.amount;
+
+    // Take the option tokens and burn
+    anchor_spl::token::burn(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Burn {
+                mint: ctx.accounts.option_mint.to_account_info(),
+                from: ctx.accounts.user_so_account.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+            &[&[
+                SO_MINT_SEED,
+                &ctx.accounts.state.key().to_bytes(),
+                &strike.to_be_bytes(),
+                &[bump],
+            ]],
+        ),
+        amount_lots,
+    )?;
+
+    // Transfer the base tokens first so they can be swapped.
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.base_vault.to_account_info(),
+                to: ctx.accounts.user_base_account.to_account_info(),
+                authority: ctx.accounts.base_vault.to_account_info(),
+            },
+            &[&[
+                SO_VAULT_SEED,
+                &ctx.accounts.state.so_name.as_bytes(),
+                &ctx.accounts.state.base_mint.key().to_bytes(),
+                &[ctx.accounts.state.vault_bump],
+            ]],
+        ),
+        amount_lots
+            .checked_mul(ctx.accounts.state.lot_size)
+            .unwrap(),
+    )?;
+
+    // Only signatures from the transaction are passed on. The vaults are PDAs
+    // of this program and are never signed for here.
+    let swap_instruction = Instruction {
+        program_id: ctx.accounts.swap_program.key(),
+        accounts: ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    let mut swap_account_infos: Vec<AccountInfo<'info>> = ctx.remaining_accounts.to_vec();
+    swap_account_infos.push(ctx.accounts.swap_program.to_account_info());
+    invoke(&swap_instruction, &swap_account_infos)?;
+
+    // Take the Quote Token payment at the strike currently in effect.
+    let current_strike: u64 = ctx
+        .accounts
+        .strike_state
+        .strike_at(Clock::get().unwrap().unix_timestamp as u64);
+    let payment: u64 = amount_lots.checked_mul(current_strike).unwrap();
+
+    // No fee when the Dual DAO or Dual Labs RM is exercising.
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.user_quote_account.owner.key(),
+        payment,
+    );
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.user_quote_account.to_account_info(),
+        ctx.accounts.project_quote_account.to_account_info(),
+        ctx.accounts.fee_quote_account.to_account_info(),
+        ctx.accounts.authority.to_account_info(),
+        &[],
+        payment,
+        fee,
+    )?;
+
+    // The swap program could have touched any of the accounts, so check the
+    // project balance directly.
+    ctx.accounts.project_quote_account.reload()?;
+    require!(
+        ctx.accounts.project_quote_account.amount
+            >= project_quote_before
+                .checked_add(payment.checked_sub(fee).unwrap())
+                .unwrap(),
+        SOErrorCode::FlashPaymentMissing
+    );
+
+    ctx.accounts.strike_state.lots_exercised = ctx
+        .accounts
+        .strike_state
+        .lots_exercised
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Exercised {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            payment,
+            fee,
+            fee_mint: ctx.accounts.state.quote_mint,
+            amount_delivered: amount_lots
+                .checked_mul(ctx.accounts.state.lot_size)
+                .unwrap(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, strike: u64)]
+pub struct FlashExercise<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Where the SO are coming from.
+    #[account(mut)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+    /// Mint is needed to burn the options.
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the payment is coming from.
+    #[account(mut)]
+    pub user_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the payment is going
+    #[account(mut)]
+    pub project_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going
+    #[account(mut)]
+    pub fee_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the payment.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    /// The base token location for this SO.
+    #[account(mut,
+        seeds = [SO_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.vault_bump,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the base tokens are going.
+    #[account(mut)]
+    pub user_base_account: Box<Account<'info, TokenAccount>>,
+
+    /// CHECK: Must be whitelisted. Called with the remaining accounts.
+    #[account(executable)]
+    pub swap_program: AccountInfo<'info>,
+
+    #[account(
+        seeds = [SWAP_WHITELIST_SEED, &swap_program.key().to_bytes()],
+        bump = swap_whitelist_entry.bump
+    )]
+    pub swap_whitelist_entry: Box<Account<'info, SwapWhitelistEntry>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> FlashExercise<'info> {
+    pub fn validate_accounts(&self, _amount: u64, _strike: u64) -> Result<()> {
+        // Verify the address of quote accounts. Because this account matches,
+        // the token type will also be verified by the token program.
+        require_keys_eq!(
+            self.state.quote_account.key(),
+            self.project_quote_account.key(),
+            SOErrorCode::IncorrectFeeAccount
+        );
+
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_quote_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        // Verify expiration
+        check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+
+        // Capped strikes pay out at most the cap, which needs the oracle.
+        require!(self.strike_state.cap == 0, SOErrorCode::CashSettledOnly);
+
+        Ok(())
+    }
+}
@@ -3,8 +3,50 @@ use anchor_spl::token::{Mint, Token};
 pub use crate::common::*;
 pub use crate::*;
 
+fn record_strike(
+    state: &mut State,
+    state_key: Pubkey,
+    strike_state: &mut StrikeState,
+    strike: u64,
+    bump: u8,
+    mint_bump: u8,
+    reverse_mint_bump: u8,
+) {
+    if state.strikes.len() < MAX_MONITORED_STRIKES {
+        state.strikes.push(strike);
+    }
+
+    strike_state.state = state_key;
+    strike_state.strike = strike;
+    strike_state.bump = bump;
+    strike_state.mint_bump = mint_bump;
+    strike_state.reverse_mint_bump = reverse_mint_bump;
+    strike_state.created_at = Clock::get().unwrap().unix_timestamp as u64;
+}
+
 pub fn init_strike(ctx: Context<InitStrike>, strike: u64) -> Result<()> {
-    ctx.accounts.state.strikes.push(strike);
+    let state_key = ctx.accounts.state.key();
+    record_strike(
+        &mut ctx.accounts.state,
+        state_key,
+        &mut ctx.accounts.strike_state,
+        strike,
+        *ctx.bumps.get("strike_state").unwrap(),
+        *ctx.bumps.get("option_mint").unwrap(),
+        0,
+    );
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        StrikeInitialized {
+            state: state_key,
+            authority: ctx.accounts.authority.key(),
+            strike,
+            option_mint: ctx.accounts.option_mint.key(),
+            reversible: false,
+        },
+    )?;
 
     Ok(())
 }
@@ -32,13 +74,28 @@ pub struct InitStrike<'info> {
         payer = authority,
         seeds = [SO_MINT_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
         bump,
-        mint::decimals = 0,
+        mint::decimals = state.option_decimals,
         mint::authority = option_mint)]
     pub option_mint: Account<'info, Mint>,
 
+    /// Accounting for this strike.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = StrikeState::SPACE
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> InitStrike<'info> {
@@ -49,15 +106,33 @@ impl<'info> InitStrike<'info> {
         // Verify that it is not already expired
         check_not_expired!(self.state.subscription_period_end);
 
-        // Make sure there are not too many strikes already.
-        require!(self.state.strikes.len() < 100, SOErrorCode::TooManyStrikes);
-
         Ok(())
     }
 }
 
 pub fn init_strike_with_payer(ctx: Context<InitStrikeWithPayer>, strike: u64) -> Result<()> {
-    ctx.accounts.state.strikes.push(strike);
+    let state_key = ctx.accounts.state.key();
+    record_strike(
+        &mut ctx.accounts.state,
+        state_key,
+        &mut ctx.accounts.strike_state,
+        strike,
+        *ctx.bumps.get("strike_state").unwrap(),
+        *ctx.bumps.get("option_mint").unwrap(),
+        0,
+    );
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        StrikeInitialized {
+            state: state_key,
+            authority: ctx.accounts.authority.key(),
+            strike,
+            option_mint: ctx.accounts.option_mint.key(),
+            reversible: false,
+        },
+    )?;
 
     Ok(())
 }
@@ -88,13 +163,28 @@ pub struct InitStrikeWithPayer<'info> {
         payer = payer,
         seeds = [SO_MINT_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
         bump,
-        mint::decimals = 0,
+        mint::decimals = state.option_decimals,
         mint::authority = option_mint)]
     pub option_mint: Account<'info, Mint>,
 
+    /// Accounting for this strike.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = StrikeState::SPACE
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> InitStrikeWithPayer<'info> {
@@ -105,16 +195,34 @@ impl<'info> InitStrikeWithPayer<'info> {
         // Verify that it is not already expired
         check_not_expired!(self.state.subscription_period_end);
 
-        // Make sure there are not too many strikes already.
-        require!(self.state.strikes.len() < 100, SOErrorCode::TooManyStrikes);
-
         Ok(())
     }
 }
 
 // Same as init strike except this also initializes the reverse
 pub fn init_strike_reversible(ctx: Context<InitStrikeReversible>, strike: u64) -> Result<()> {
-    ctx.accounts.state.strikes.push(strike);
+    let state_key = ctx.accounts.state.key();
+    record_strike(
+        &mut ctx.accounts.state,
+        state_key,
+        &mut ctx.accounts.strike_state,
+        strike,
+        *ctx.bumps.get("strike_state").unwrap(),
+        *ctx.bumps.get("option_mint").unwrap(),
+        *ctx.bumps.get("reverse_option_mint").unwrap(),
+    );
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        StrikeInitialized {
+            state: state_key,
+            authority: ctx.accounts.authority.key(),
+            strike,
+            option_mint: ctx.accounts.option_mint.key(),
+            reversible: true,
+        },
+    )?;
 
     Ok(())
 }
@@ -145,7 +253,7 @@ pub struct InitStrikeReversible<'info> {
         payer = payer,
         seeds = [SO_REVERSE_MINT_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
         bump,
-        mint::decimals = 0,
+        mint::decimals = state.option_decimals,
         mint::authority = reverse_option_mint)]
     pub reverse_option_mint: Account<'info, Mint>,
 
@@ -154,13 +262,28 @@ pub struct InitStrikeReversible<'info> {
         payer = payer,
         seeds = [SO_MINT_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
         bump,
-        mint::decimals = 0,
+        mint::decimals = state.option_decimals,
         mint::authority = option_mint)]
     pub option_mint: Account<'info, Mint>,
 
+    /// Accounting for this strike.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = StrikeState::SPACE
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> InitStrikeReversible<'info> {
@@ -171,9 +294,78 @@ impl<'info> InitStrikeReversible<'info> {
         // Verify that it is not already expired
         check_not_expired!(self.state.subscription_period_end);
 
-        // Make sure there are not too many strikes already.
-        require!(self.state.strikes.len() < 100, SOErrorCode::TooManyStrikes);
-
         Ok(())
     }
 }
+
+// Creates the StrikeState for a strike that was initialized before strikes had
+// their own accounts. Permissionless since everything is derived from the
+// existing mints.
+//
+// Exercised options were burned, so the current supply is what is still
+// outstanding rather than everything ever issued. lots_issued starts at that
+// supply and lots_exercised at zero, so both leave out exercises from before
+// this point.
+pub fn init_strike_state(ctx: Context<InitStrikeState>, strike: u64) -> Result<()> {
+    check_mint!(ctx, strike, mint_bump);
+
+    // Only the program can create an account at the reverse mint address, so
+    // the strike is reversible exactly when that account exists.
+    let reverse_mint_bump: u8 = if ctx.accounts.reverse_option_mint.data_is_empty() {
+        0
+    } else {
+        *ctx.bumps.get("reverse_option_mint").unwrap()
+    };
+
+    let strike_state = &mut ctx.accounts.strike_state;
+    strike_state.state = ctx.accounts.state.key();
+    strike_state.strike = strike;
+    strike_state.bump = *ctx.bumps.get("strike_state").unwrap();
+    strike_state.mint_bump = mint_bump;
+    strike_state.reverse_mint_bump = reverse_mint_bump;
+    strike_state.lots_issued = ctx.accounts.option_mint.supply;
+    strike_state.created_at = Clock::get().unwrap().unix_timestamp as u64;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct InitStrikeState<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Existing option mint for the strike.
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Address of the reverse option mint, which only exists if the
+    /// strike is reversible. Always required so a reversible strike cannot be
+    /// recorded as not reversible.
+    #[account(
+        seeds = [SO_REVERSE_MINT_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump
+    )]
+    pub reverse_option_mint: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = StrikeState::SPACE
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    pub system_program: Program<'info, System>,
+}
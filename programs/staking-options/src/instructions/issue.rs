@@ -2,37 +2,89 @@ use anchor_spl::token::{Mint, Token, TokenAccount};
 
 pub use crate::*;
 
-pub fn issue(ctx: Context<Issue>, amount: u64, strike: u64) -> Result<()> {
-    // Verify the mint is at the right address
-    check_mint!(ctx, strike, bump);
-
-    let amount_lots: u64 = amount.checked_div(ctx.accounts.state.lot_size).unwrap();
+// Calls use up the full amount of base atoms requested. Puts use up the quote
+// atoms needed to pay the strike on every lot.
+fn collateral_for_issue(state: &State, amount: u64, strike: u64) -> u64 {
+    match state.option_type {
+        OptionType::Call => amount,
+        OptionType::Put => {
+            state.collateral_for_lots(amount.checked_div(state.lot_size).unwrap(), strike)
+        }
+    }
+}
 
+// Mints options on the strike and counts them as issued. Shared by every
+// instruction that hands out options.
+pub fn mint_options<'info>(
+    token_program: AccountInfo<'info>,
+    option_mint: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    state: Pubkey,
+    strike: u64,
+    mint_bump: u8,
+    strike_state: &mut StrikeState,
+    amount_lots: u64,
+) -> Result<()> {
     anchor_spl::token::mint_to(
         CpiContext::new_with_signer(
-            ctx.accounts.token_program.to_account_info(),
+            token_program,
             anchor_spl::token::MintTo {
-                mint: ctx.accounts.option_mint.to_account_info(),
-                to: ctx.accounts.user_so_account.to_account_info(),
-                authority: ctx.accounts.option_mint.to_account_info(),
+                mint: option_mint.clone(),
+                to,
+                authority: option_mint,
             },
             &[&[
                 SO_MINT_SEED,
-                &ctx.accounts.state.key().to_bytes(),
+                &state.to_bytes(),
                 &strike.to_be_bytes(),
-                &[bump],
+                &[mint_bump],
             ]],
         ),
         amount_lots,
     )?;
 
+    strike_state.lots_issued = strike_state.lots_issued.checked_add(amount_lots).unwrap();
+
+    Ok(())
+}
+
+pub fn issue(ctx: Context<Issue>, amount: u64, strike: u64) -> Result<()> {
+    // Verify the mint is at the right address
+    check_mint!(ctx, strike, bump);
+
+    let amount_lots: u64 = amount.checked_div(ctx.accounts.state.lot_size).unwrap();
+
+    mint_options(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.option_mint.to_account_info(),
+        ctx.accounts.user_so_account.to_account_info(),
+        ctx.accounts.state.key(),
+        strike,
+        bump,
+        &mut ctx.accounts.strike_state,
+        amount_lots,
+    )?;
+
     // Update state to reflect the number of available tokens
     ctx.accounts.state.options_available = ctx
         .accounts
         .state
         .options_available
-        .checked_sub(amount)
+        .checked_sub(collateral_for_issue(&ctx.accounts.state, amount, strike))
         .unwrap();
+    ctx.accounts.state.options_committed = true;
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        OptionsIssued {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            recipient: ctx.accounts.user_so_account.key(),
+        },
+    )?;
 
     Ok(())
 }
@@ -56,20 +108,31 @@ pub struct Issue<'info> {
     #[account(mut)]
     pub option_mint: Account<'info, Mint>,
 
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
     /// Where the options will be sent.
     #[account(mut)]
     pub user_so_account: Box<Account<'info, TokenAccount>>,
 
     pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> Issue<'info> {
-    pub fn validate_accounts(&self, amount: u64) -> Result<()> {
+    pub fn validate_accounts(&self, amount: u64, strike: u64) -> Result<()> {
         // Verify the authority signer matches state authority. in this case, it
         // can be the issue authority or the so authority.
         require!(
-            self.authority.key.to_bytes() == self.state.authority.to_bytes()
-                || self.authority.key.to_bytes() == self.state.issue_authority.to_bytes(),
+            self.state.is_issuer(self.authority.key()),
             SOErrorCode::IncorrectAuthority
         );
 
@@ -78,7 +141,7 @@ impl<'info> Issue<'info> {
 
         // Make sure there are enough tokens to back the options.
         require!(
-            self.state.options_available >= amount,
+            self.state.options_available >= collateral_for_issue(&self.state, amount, strike),
             SOErrorCode::NotEnoughTokens
         );
 
@@ -0,0 +1,268 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_merkle_root(
+    ctx: Context<SetMerkleRoot>,
+    strike: u64,
+    root: [u8; 32],
+    num_leaves: u64,
+    total_lots: u64,
+) -> Result<()> {
+    let distributor = &mut ctx.accounts.merkle_distributor;
+    distributor.state = ctx.accounts.state.key();
+    distributor.strike = strike;
+    distributor.bump = *ctx.bumps.get("merkle_distributor").unwrap();
+    distributor.root = root;
+    distributor.num_leaves = num_leaves;
+    distributor.total_lots = total_lots;
+    distributor.lots_claimed = 0;
+    distributor.claimed_bitmap = vec![0; MerkleDistributor::bitmap_len(num_leaves)];
+
+    ctx.accounts
+        .state
+        .reserve_collateral(&mut ctx.accounts.strike_state, total_lots)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64, root: [u8; 32], num_leaves: u64)]
+pub struct SetMerkleRoot<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The strike needs to exist before distributing options on it.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_MERKLE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = MerkleDistributor::SPACE + MerkleDistributor::bitmap_len(num_leaves)
+    )]
+    pub merkle_distributor: Box<Account<'info, MerkleDistributor>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetMerkleRoot<'info> {
+    pub fn validate_accounts(
+        &self,
+        _strike: u64,
+        _root: [u8; 32],
+        num_leaves: u64,
+        total_lots: u64,
+    ) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        require!(num_leaves > 0 && total_lots > 0, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
+
+pub fn claim(
+    ctx: Context<Claim>,
+    strike: u64,
+    index: u64,
+    lots: u64,
+    _proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    check_mint!(ctx, strike, bump);
+
+    ctx.accounts.merkle_distributor.set_claimed(index);
+
+    mint_options(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.option_mint.to_account_info(),
+        ctx.accounts.claimant_so_account.to_account_info(),
+        ctx.accounts.state.key(),
+        strike,
+        bump,
+        &mut ctx.accounts.strike_state,
+        lots,
+    )?;
+
+    ctx.accounts.merkle_distributor.lots_claimed = ctx
+        .accounts
+        .merkle_distributor
+        .lots_claimed
+        .checked_add(lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        OptionsIssued {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.claimant.key(),
+            strike,
+            amount_lots: lots,
+            recipient: ctx.accounts.claimant_so_account.key(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct Claim<'info> {
+    /// Recipient in the merkle tree.
+    pub claimant: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_MERKLE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = merkle_distributor.bump
+    )]
+    pub merkle_distributor: Box<Account<'info, MerkleDistributor>>,
+
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the options will be sent.
+    #[account(mut)]
+    pub claimant_so_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> Claim<'info> {
+    pub fn validate_accounts(
+        &self,
+        _strike: u64,
+        index: u64,
+        lots: u64,
+        proof: &[[u8; 32]],
+    ) -> Result<()> {
+        // Verify expiration
+        check_not_expired!(self.state.option_expiration);
+
+        require!(
+            index < self.merkle_distributor.num_leaves,
+            SOErrorCode::InvalidProof
+        );
+        require!(
+            !self.merkle_distributor.is_claimed(index),
+            SOErrorCode::AlreadyClaimed
+        );
+
+        let leaf: [u8; 32] = merkle_leaf(index, &self.claimant.key(), lots);
+        require!(
+            verify_merkle_proof(proof, self.merkle_distributor.root, leaf),
+            SOErrorCode::InvalidProof
+        );
+
+        // A bad root cannot mint more than was reserved.
+        require!(
+            self.merkle_distributor
+                .lots_claimed
+                .checked_add(lots)
+                .unwrap()
+                <= self.merkle_distributor.total_lots,
+            SOErrorCode::NotEnoughTokens
+        );
+
+        Ok(())
+    }
+}
+
+// Ends the airdrop. Collateral for unclaimed lots is returned to
+// options_available.
+pub fn close_merkle_distributor(ctx: Context<CloseMerkleDistributor>, strike: u64) -> Result<()> {
+    let unclaimed_lots: u64 = ctx
+        .accounts
+        .merkle_distributor
+        .total_lots
+        .checked_sub(ctx.accounts.merkle_distributor.lots_claimed)
+        .unwrap();
+    ctx.accounts
+        .state
+        .release_collateral(unclaimed_lots, strike);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CloseMerkleDistributor<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        close = authority,
+        seeds = [SO_MERKLE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = merkle_distributor.bump
+    )]
+    pub merkle_distributor: Box<Account<'info, MerkleDistributor>>,
+}
+
+impl<'info> CloseMerkleDistributor<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        check_not_expired!(self.state.option_expiration);
+
+        Ok(())
+    }
+}
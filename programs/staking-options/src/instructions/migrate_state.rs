@@ -0,0 +1,116 @@
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+// Brings a State created by an older config up to the newest layout. SOs
+// created with config and config_v2 do not have a quote vault, so one is
+// created here which makes them usable with reversible strikes.
+//
+// The State is resized before it is deserialized, since an older account may
+// be too short to hold the newest layout.
+pub fn migrate_state(ctx: Context<MigrateState>, _so_name: String) -> Result<()> {
+    let state_info: AccountInfo = ctx.accounts.state.to_account_info();
+    if state_info.data_len() < State::SPACE {
+        let rent_needed: u64 = Rent::get()?
+            .minimum_balance(State::SPACE)
+            .saturating_sub(state_info.lamports());
+        if rent_needed > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: state_info.clone(),
+                    },
+                ),
+                rent_needed,
+            )?;
+        }
+        state_info.realloc(State::SPACE, false)?;
+    }
+
+    let mut state: State = State::try_deserialize(&mut &state_info.data.borrow()[..])?;
+    require_keys_eq!(
+        ctx.accounts.authority.key(),
+        state.authority,
+        SOErrorCode::IncorrectAuthority
+    );
+    require_keys_eq!(
+        ctx.accounts.quote_mint.key(),
+        state.quote_mint,
+        SOErrorCode::InvalidMint
+    );
+    // Do not downgrade a State written by a newer program.
+    require!(state.version <= STATE_VERSION, SOErrorCode::InvalidVersion);
+
+    // For SOs from config and config_v2, these bytes held the quote decimals
+    // and have to be overwritten with the actual bump.
+    state.quote_vault_bump = *ctx.bumps.get("quote_vault").unwrap();
+
+    // Fields added in each version. issue_authority and deal_type predate
+    // versioning and are zero, which is their default, when never set.
+    if state.version < 2 {
+        state.exercise_start = 0;
+        // Mints of older SOs were created with zero decimals.
+        state.option_decimals = 0;
+    }
+    if state.version < 3 {
+        state.option_type = OptionType::Call;
+        state.put_vault_bump = 0;
+    }
+    if state.version < 4 {
+        state.oracle = Pubkey::default();
+        state.oracle_max_age_sec = 0;
+    }
+    if state.version < 5 {
+        state.exercise_windows = vec![];
+    }
+    if state.version < 6 {
+        state.net_exercise_haircut_bps = 0;
+        // Whether options were issued is not known, so any SO with strikes is
+        // treated as committed.
+        state.options_committed = !state.strikes.is_empty();
+    }
+    state.version = STATE_VERSION;
+
+    state.try_serialize(&mut &mut state_info.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(so_name: String)]
+pub struct MigrateState<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Deserialized as a State in the handler, after it is resized.
+    #[account(mut,
+        seeds = [SO_CONFIG_SEED, so_name.as_bytes(), &base_mint.key().to_bytes()],
+        bump,
+        owner = crate::ID,
+    )]
+    pub state: UncheckedAccount<'info>,
+
+    /// Where the quote tokens are held for reversible options. Already exists
+    /// for SOs created with config_v3.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [SO_REVERSE_VAULT_SEED, so_name.as_bytes(), &base_mint.key().to_bytes()],
+        bump,
+        token::mint = quote_mint,
+        token::authority = quote_vault)]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    pub base_mint: Box<Account<'info, Mint>>,
+    pub quote_mint: Box<Account<'info, Mint>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
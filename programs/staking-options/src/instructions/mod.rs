@@ -1,17 +1,51 @@
 pub mod add_tokens;
+pub mod auction;
+pub mod auto_exercise;
+pub mod barrier;
 pub mod config;
 pub mod exercise;
+pub mod fee_schedule;
+pub mod flash_exercise;
 pub mod init_strike;
 pub mod issue;
+pub mod merkle_distributor;
+pub mod migrate_state;
 pub mod modify_expiration;
 pub mod name_token;
+pub mod quote;
+pub mod set_deal_type;
+pub mod set_exercise_schedule;
+pub mod set_net_exercise_haircut;
+pub mod set_oracle;
+pub mod set_strike_cap;
+pub mod set_strike_schedule;
+pub mod shop;
+pub mod stake_pool;
+pub mod vesting;
 pub mod withdraw;
 
 pub use add_tokens::*;
+pub use auction::*;
+pub use auto_exercise::*;
+pub use barrier::*;
 pub use config::*;
 pub use exercise::*;
+pub use fee_schedule::*;
+pub use flash_exercise::*;
 pub use init_strike::*;
 pub use issue::*;
+pub use merkle_distributor::*;
+pub use migrate_state::*;
 pub use modify_expiration::*;
 pub use name_token::*;
+pub use quote::*;
+pub use set_deal_type::*;
+pub use set_exercise_schedule::*;
+pub use set_net_exercise_haircut::*;
+pub use set_oracle::*;
+pub use set_strike_cap::*;
+pub use set_strike_schedule::*;
+pub use shop::*;
+pub use stake_pool::*;
+pub use vesting::*;
 pub use withdraw::*;
@@ -17,11 +17,23 @@ pub fn modify_expiration(
     assert!(ctx.accounts.user_so_account.owner == *ctx.accounts.authority.key);
     assert!(ctx.accounts.user_so_account.amount == ctx.accounts.option_mint.supply);
 
+    let old_expiration: u64 = ctx.accounts.state.option_expiration;
     ctx.accounts.state.option_expiration = new_expiration_unix_sec;
     if ctx.accounts.state.subscription_period_end > new_expiration_unix_sec {
         ctx.accounts.state.subscription_period_end = new_expiration_unix_sec;
     }
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        ExpirationModified {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            old_expiration,
+            new_expiration: new_expiration_unix_sec,
+        },
+    )?;
+
     Ok(())
 }
 
@@ -46,4 +58,9 @@ pub struct ModifyExpiration<'info> {
     pub user_so_account: Box<Account<'info, TokenAccount>>,
     /// Mint is needed to get the number of outstanding options.
     pub option_mint: Box<Account<'info, Mint>>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
@@ -0,0 +1,239 @@
+use anchor_spl::token::TokenAccount;
+
+pub use crate::common::*;
+pub use crate::*;
+
+// Returned from the quote instructions. These do not transfer anything, so
+// they can be simulated to show what an instruction will cost with the same
+// math the program uses.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ExerciseQuote {
+    // Quote atoms for the strike. Paid by the holder for calls, received for
+    // puts and reverse exercise.
+    pub payment: u64,
+    // Quote atoms of the payment that go to the DUAL DAO.
+    pub fee: u64,
+    // Base atoms received by the holder for calls, delivered by the holder
+    // for puts and reverse exercise.
+    pub base_delivered: u64,
+    pub fee_bps: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawAllQuote {
+    pub base_withdrawn: u64,
+    // Quote atoms sent to the project after the fee.
+    pub quote_withdrawn: u64,
+    pub fee: u64,
+    pub fee_bps: u64,
+}
+
+// Quote for exercise and exercise_put. The exerciser is the owner of the
+// account the payment comes from, since some owners are fee exempt.
+pub fn quote_exercise(
+    ctx: Context<QuoteExercise>,
+    amount_lots: u64,
+    _strike: u64,
+    exerciser: Pubkey,
+) -> Result<ExerciseQuote> {
+    let state: &State = &ctx.accounts.state;
+
+    // Puts pay the strike they were created with. Calls pay the strike
+    // currently in effect.
+    let current_strike: u64 = match state.option_type {
+        OptionType::Call => ctx
+            .accounts
+            .strike_state
+            .strike_at(Clock::get().unwrap().unix_timestamp as u64),
+        OptionType::Put => ctx.accounts.strike_state.strike,
+    };
+    let payment: u64 = amount_lots.checked_mul(current_strike).unwrap();
+
+    Ok(ExerciseQuote {
+        payment,
+        fee: calculate_fee(&ctx.accounts.fee_schedule, state, exerciser, payment),
+        base_delivered: amount_lots.checked_mul(state.lot_size).unwrap(),
+        fee_bps: effective_fee_bps(&ctx.accounts.fee_schedule, state, exerciser),
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(amount_lots: u64, strike: u64)]
+pub struct QuoteExercise<'info> {
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Accounting for this strike.
+    #[account(
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Determines the fee charged on the payment.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+}
+
+impl<'info> QuoteExercise<'info> {
+    // Same gating as exercise and exercise_put, so a quote is only returned
+    // when the exercise would be allowed right now.
+    pub fn validate_accounts(
+        &self,
+        _amount_lots: u64,
+        _strike: u64,
+        _exerciser: Pubkey,
+    ) -> Result<()> {
+        check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+        require!(
+            self.strike_state.barrier_allows_exercise(),
+            SOErrorCode::BarrierBlocksExercise
+        );
+
+        // Capped calls can only be cash exercised, which is not quoted here.
+        if self.state.option_type == OptionType::Call {
+            require!(self.strike_state.cap == 0, SOErrorCode::CashSettledOnly);
+        }
+
+        Ok(())
+    }
+}
+
+// Reverse exercise returns the strike without a fee. The fee on reversible
+// exercises is taken in withdraw_all.
+pub fn quote_reverse_exercise(
+    ctx: Context<QuoteReverseExercise>,
+    amount_lots: u64,
+    strike: u64,
+) -> Result<ExerciseQuote> {
+    Ok(ExerciseQuote {
+        payment: amount_lots.checked_mul(strike).unwrap(),
+        fee: 0,
+        base_delivered: amount_lots
+            .checked_mul(ctx.accounts.state.lot_size)
+            .unwrap(),
+        fee_bps: 0,
+    })
+}
+
+#[derive(Accounts)]
+#[instruction(amount_lots: u64, strike: u64)]
+pub struct QuoteReverseExercise<'info> {
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Accounting for this strike.
+    #[account(
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+}
+
+impl<'info> QuoteReverseExercise<'info> {
+    // Same gating as reverse_exercise, so a quote is only returned when the
+    // reverse exercise would be allowed right now.
+    pub fn validate_accounts(&self, _amount_lots: u64, _strike: u64) -> Result<()> {
+        check_not_expired!(self.state.option_expiration);
+        check_exercisable!(self.state);
+
+        // reverse_exercise needs the reverse mint, which only reversible
+        // strikes have.
+        require!(
+            self.strike_state.reverse_mint_bump != 0,
+            SOErrorCode::InvalidMint
+        );
+
+        Ok(())
+    }
+}
+
+pub fn quote_withdraw_all(ctx: Context<QuoteWithdrawAll>) -> Result<WithdrawAllQuote> {
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let state: &State = &ctx.accounts.state;
+
+    // Same split as withdraw_all. Before expiration only the base not backing
+    // options comes out and the quote stays for reverse exercise.
+    if now <= state.option_expiration {
+        return Ok(WithdrawAllQuote {
+            base_withdrawn: state.options_available,
+            quote_withdrawn: 0,
+            fee: 0,
+            fee_bps: 0,
+        });
+    }
+
+    let quote_owner: Pubkey = ctx.accounts.quote_account.owner;
+    let total_quote_tokens: u64 = ctx.accounts.quote_vault.amount;
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        state,
+        quote_owner,
+        total_quote_tokens,
+    );
+
+    Ok(WithdrawAllQuote {
+        base_withdrawn: ctx.accounts.base_vault.amount,
+        quote_withdrawn: total_quote_tokens.checked_sub(fee).unwrap(),
+        fee,
+        fee_bps: effective_fee_bps(&ctx.accounts.fee_schedule, state, quote_owner),
+    })
+}
+
+#[derive(Accounts)]
+pub struct QuoteWithdrawAll<'info> {
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The base token location for this SO.
+    #[account(
+        seeds = [SO_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.vault_bump,
+    )]
+    pub base_vault: Box<Account<'info, TokenAccount>>,
+
+    /// The quote token location for this SO.
+    #[account(
+        seeds = [SO_REVERSE_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.quote_vault_bump,
+    )]
+    pub quote_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where withdraw_all would send the quote tokens.
+    pub quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the quote tokens.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+}
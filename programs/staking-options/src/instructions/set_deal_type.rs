@@ -0,0 +1,45 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_deal_type(ctx: Context<SetDealType>, deal_type: DealType) -> Result<()> {
+    ctx.accounts.state.deal_type = deal_type;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(deal_type: DealType)]
+pub struct SetDealType<'info> {
+    /// Must be the fee schedule authority. The SO authority cannot give itself
+    /// a discount.
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+}
+
+impl<'info> SetDealType<'info> {
+    pub fn validate_accounts(&self) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.fee_schedule.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_exercise_schedule(
+    ctx: Context<SetExerciseSchedule>,
+    exercise_start: u64,
+    exercise_windows: Vec<ExerciseWindow>,
+) -> Result<()> {
+    ctx.accounts.state.exercise_start = exercise_start;
+    ctx.accounts.state.exercise_windows = exercise_windows;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetExerciseSchedule<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+}
+
+impl<'info> SetExerciseSchedule<'info> {
+    pub fn validate_accounts(
+        &self,
+        exercise_start: u64,
+        exercise_windows: &[ExerciseWindow],
+    ) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Holders should know when they can exercise by the time options are
+        // issued, so the schedule is fixed once any are.
+        check_not_expired!(self.state.subscription_period_end);
+        require!(!self.state.options_committed, SOErrorCode::InvalidConfig);
+
+        // Windows and options_committed are stored on the State, so older
+        // accounts need to be migrated first.
+        require!(self.state.version >= 6, SOErrorCode::InvalidVersion);
+
+        require!(
+            exercise_start <= self.state.option_expiration,
+            SOErrorCode::InvalidConfig
+        );
+        require!(
+            exercise_windows.len() <= MAX_EXERCISE_WINDOWS,
+            SOErrorCode::InvalidConfig
+        );
+        // Windows that already ended could never be used.
+        let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+        for (i, window) in exercise_windows.iter().enumerate() {
+            require!(
+                window.start <= window.end
+                    && now <= window.end
+                    && window.end <= self.state.option_expiration,
+                SOErrorCode::InvalidConfig
+            );
+            if i > 0 {
+                require!(
+                    exercise_windows[i - 1].end < window.start,
+                    SOErrorCode::InvalidConfig
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_net_exercise_haircut(
+    ctx: Context<SetNetExerciseHaircut>,
+    haircut_bps: u16,
+) -> Result<()> {
+    ctx.accounts.state.net_exercise_haircut_bps = haircut_bps;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct SetNetExerciseHaircut<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+}
+
+impl<'info> SetNetExerciseHaircut<'info> {
+    pub fn validate_accounts(&self, haircut_bps: u16) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Holders should know how options settle by the time they are issued,
+        // so the haircut is fixed once any are.
+        check_not_expired!(self.state.subscription_period_end);
+        require!(!self.state.options_committed, SOErrorCode::InvalidConfig);
+
+        // The haircut was added without a version of its own, so only States
+        // from version 6 are known to have it.
+        require!(self.state.version >= 6, SOErrorCode::InvalidVersion);
+
+        // A full haircut would make the withheld base infinite.
+        require!(
+            (haircut_bps as u64) < MAX_FEE_BPS,
+            SOErrorCode::InvalidConfig
+        );
+
+        Ok(())
+    }
+}
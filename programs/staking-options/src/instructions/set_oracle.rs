@@ -0,0 +1,52 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_oracle(ctx: Context<SetOracle>, max_age_sec: u64) -> Result<()> {
+    // Verify that the account is a live price feed.
+    load_oracle_price(&ctx.accounts.oracle, max_age_sec)?;
+
+    ctx.accounts.state.oracle = ctx.accounts.oracle.key();
+    ctx.accounts.state.oracle_max_age_sec = max_age_sec;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(max_age_sec: u64)]
+pub struct SetOracle<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// CHECK: Parsed as a Pyth price account in the handler.
+    pub oracle: AccountInfo<'info>,
+}
+
+impl<'info> SetOracle<'info> {
+    pub fn validate_accounts(&self, max_age_sec: u64) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Holders should know how options settle by the time they are issued,
+        // so the oracle is fixed once any are.
+        check_not_expired!(self.state.subscription_period_end);
+        require!(self.state.version >= 6, SOErrorCode::InvalidVersion);
+        require!(!self.state.options_committed, SOErrorCode::InvalidConfig);
+
+        require!(max_age_sec > 0, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
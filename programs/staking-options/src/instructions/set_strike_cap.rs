@@ -0,0 +1,66 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_strike_cap(ctx: Context<SetStrikeCap>, _strike: u64, cap: u64) -> Result<()> {
+    ctx.accounts.strike_state.cap = cap;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct SetStrikeCap<'info> {
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+}
+
+impl<'info> SetStrikeCap<'info> {
+    pub fn validate_accounts(&self, strike: u64, cap: u64) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        require!(
+            !self.strike_state.terms_locked(),
+            SOErrorCode::InvalidConfig
+        );
+
+        // Capped options are settled in base at the oracle price, which is
+        // only done for calls that cannot be reversed.
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+        require!(
+            self.strike_state.reverse_mint_bump == 0,
+            SOErrorCode::InvalidConfig
+        );
+        require!(
+            self.state.oracle != Pubkey::default(),
+            SOErrorCode::InvalidOracle
+        );
+
+        // Zero removes the cap.
+        require!(cap == 0 || cap > strike, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
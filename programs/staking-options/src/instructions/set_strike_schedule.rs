@@ -0,0 +1,88 @@
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn set_strike_schedule(
+    ctx: Context<SetStrikeSchedule>,
+    _strike: u64,
+    schedule_kind: StrikeScheduleKind,
+    breakpoints: Vec<StrikeBreakpoint>,
+) -> Result<()> {
+    ctx.accounts.strike_state.schedule_kind = schedule_kind;
+    ctx.accounts.strike_state.strike_schedule = breakpoints;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64, schedule_kind: StrikeScheduleKind, breakpoints: Vec<StrikeBreakpoint>)]
+pub struct SetStrikeSchedule<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// Grows to fit the breakpoints.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump,
+        realloc = StrikeState::SPACE + breakpoints.len() * (8 + 8),
+        realloc::payer = payer,
+        realloc::zero = false
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetStrikeSchedule<'info> {
+    pub fn validate_accounts(&self, breakpoints: &[StrikeBreakpoint]) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        require!(
+            !self.strike_state.terms_locked(),
+            SOErrorCode::InvalidConfig
+        );
+
+        // Reverse exercise refunds the strike that was paid, and puts are
+        // backed by the strike, so both need a fixed strike.
+        require!(
+            self.state.option_type == OptionType::Call,
+            SOErrorCode::WrongOptionType
+        );
+        require!(
+            self.strike_state.reverse_mint_bump == 0,
+            SOErrorCode::InvalidConfig
+        );
+
+        require!(
+            breakpoints.len() <= MAX_STRIKE_BREAKPOINTS,
+            SOErrorCode::InvalidConfig
+        );
+        for (i, breakpoint) in breakpoints.iter().enumerate() {
+            require!(breakpoint.strike > 0, SOErrorCode::InvalidConfig);
+            if i > 0 {
+                require!(
+                    breakpoints[i - 1].timestamp < breakpoint.timestamp,
+                    SOErrorCode::InvalidConfig
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
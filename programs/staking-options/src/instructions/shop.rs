@@ -0,0 +1,344 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn create_shop(
+    ctx: Context<CreateShop>,
+    strike: u64,
+    premium_per_lot: u64,
+    max_lots: u64,
+) -> Result<()> {
+    let shop = &mut ctx.accounts.shop;
+    shop.state = ctx.accounts.state.key();
+    shop.strike = strike;
+    shop.bump = *ctx.bumps.get("shop").unwrap();
+    shop.premium_per_lot = premium_per_lot;
+    shop.max_lots = max_lots;
+    shop.lots_sold = 0;
+
+    ctx.accounts
+        .state
+        .reserve_collateral(&mut ctx.accounts.strike_state, max_lots)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CreateShop<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The strike needs to exist before selling options on it.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_SHOP_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = Shop::SPACE
+    )]
+    pub shop: Box<Account<'info, Shop>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateShop<'info> {
+    pub fn validate_accounts(
+        &self,
+        _strike: u64,
+        premium_per_lot: u64,
+        max_lots: u64,
+    ) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        require!(
+            premium_per_lot > 0 && max_lots > 0,
+            SOErrorCode::InvalidConfig
+        );
+
+        Ok(())
+    }
+}
+
+pub fn buy_from_shop(
+    ctx: Context<BuyFromShop>,
+    strike: u64,
+    amount_lots: u64,
+    _max_premium_per_lot: u64,
+) -> Result<()> {
+    check_mint!(ctx, strike, bump);
+
+    // Take the premium.
+    let payment: u64 = amount_lots
+        .checked_mul(ctx.accounts.shop.premium_per_lot)
+        .unwrap();
+    let fee: u64 = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.buyer_quote_account.owner.key(),
+        payment,
+    );
+    transfer_payment_with_fee(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.buyer_quote_account.to_account_info(),
+        ctx.accounts.project_quote_account.to_account_info(),
+        ctx.accounts.fee_quote_account.to_account_info(),
+        ctx.accounts.authority.to_account_info(),
+        &[],
+        payment,
+        fee,
+    )?;
+
+    mint_options(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.option_mint.to_account_info(),
+        ctx.accounts.buyer_so_account.to_account_info(),
+        ctx.accounts.state.key(),
+        strike,
+        bump,
+        &mut ctx.accounts.strike_state,
+        amount_lots,
+    )?;
+
+    ctx.accounts.shop.lots_sold = ctx
+        .accounts
+        .shop
+        .lots_sold
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        OptionsIssued {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            recipient: ctx.accounts.buyer_so_account.key(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct BuyFromShop<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_SHOP_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = shop.bump
+    )]
+    pub shop: Box<Account<'info, Shop>>,
+
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the options will be sent.
+    #[account(mut)]
+    pub buyer_so_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the premium is coming from.
+    #[account(mut)]
+    pub buyer_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the premium is going
+    #[account(mut)]
+    pub project_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Where the fee is going
+    #[account(mut)]
+    pub fee_quote_account: Box<Account<'info, TokenAccount>>,
+
+    /// Determines the fee charged on the premium.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> BuyFromShop<'info> {
+    pub fn validate_accounts(
+        &self,
+        _strike: u64,
+        amount_lots: u64,
+        max_premium_per_lot: u64,
+    ) -> Result<()> {
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        // Protects buyers from the premium being changed under them.
+        require!(
+            self.shop.premium_per_lot <= max_premium_per_lot,
+            SOErrorCode::PriceTooHigh
+        );
+
+        require!(amount_lots > 0, SOErrorCode::InvalidConfig);
+        require!(
+            self.shop.lots_sold.checked_add(amount_lots).unwrap() <= self.shop.max_lots,
+            SOErrorCode::NotEnoughTokens
+        );
+
+        validate_premium_account(&self.state, &self.project_quote_account)?;
+
+        // Verify that it is owned by DUAL.
+        require_eq!(
+            self.fee_quote_account.owner.key().to_string(),
+            DUAL_DAO_ADDRESS
+        );
+
+        Ok(())
+    }
+}
+
+// Reprices the lots left in the shop. A buy signed before the change is
+// rejected if the new premium is above its max_premium_per_lot.
+pub fn set_shop_premium(
+    ctx: Context<SetShopPremium>,
+    _strike: u64,
+    premium_per_lot: u64,
+) -> Result<()> {
+    ctx.accounts.shop.premium_per_lot = premium_per_lot;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct SetShopPremium<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_SHOP_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = shop.bump
+    )]
+    pub shop: Box<Account<'info, Shop>>,
+}
+
+impl<'info> SetShopPremium<'info> {
+    pub fn validate_accounts(&self, _strike: u64, premium_per_lot: u64) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        require!(premium_per_lot > 0, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
+
+// Stops sales. Collateral for unsold lots is returned to options_available.
+pub fn close_shop(ctx: Context<CloseShop>, strike: u64) -> Result<()> {
+    let unsold_lots: u64 = ctx
+        .accounts
+        .shop
+        .max_lots
+        .checked_sub(ctx.accounts.shop.lots_sold)
+        .unwrap();
+    ctx.accounts.state.release_collateral(unsold_lots, strike);
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CloseShop<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        close = authority,
+        seeds = [SO_SHOP_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = shop.bump
+    )]
+    pub shop: Box<Account<'info, Shop>>,
+}
+
+impl<'info> CloseShop<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        check_not_expired!(self.state.option_expiration);
+
+        Ok(())
+    }
+}
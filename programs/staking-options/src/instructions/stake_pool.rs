@@ -0,0 +1,477 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn create_stake_pool(
+    ctx: Context<CreateStakePool>,
+    strike: u64,
+    total_lots: u64,
+) -> Result<()> {
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+
+    let stake_pool = &mut ctx.accounts.stake_pool;
+    stake_pool.state = ctx.accounts.state.key();
+    stake_pool.strike = strike;
+    stake_pool.bump = *ctx.bumps.get("stake_pool").unwrap();
+    stake_pool.stake_mint = ctx.accounts.stake_mint.key();
+    stake_pool.stake_vault_bump = *ctx.bumps.get("stake_vault").unwrap();
+    stake_pool.total_lots = total_lots;
+    stake_pool.lots_claimed = 0;
+    stake_pool.end_time = ctx.accounts.state.subscription_period_end;
+    stake_pool.total_staked = 0;
+    stake_pool.stake_seconds = 0;
+    stake_pool.last_update = now;
+    stake_pool.stake_seconds_claimed = 0;
+    stake_pool.closed = false;
+
+    ctx.accounts
+        .state
+        .reserve_collateral(&mut ctx.accounts.strike_state, total_lots)?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CreateStakePool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The strike needs to exist before staking for options on it.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_STAKE_POOL_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump,
+        space = StakePool::SPACE
+    )]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+
+    /// Token that users deposit to earn options.
+    pub stake_mint: Box<Account<'info, Mint>>,
+
+    /// Where the staked tokens are held.
+    #[account(
+        init,
+        payer = payer,
+        seeds = [SO_STAKE_VAULT_SEED, &stake_pool.key().to_bytes()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = stake_vault)]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+impl<'info> CreateStakePool<'info> {
+    pub fn validate_accounts(&self, _strike: u64, total_lots: u64) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        require!(total_lots > 0, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
+
+pub fn stake(ctx: Context<Stake>, _strike: u64, amount: u64) -> Result<()> {
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+
+    // Bring both up to now before the balance changes.
+    let end_time: u64 = ctx.accounts.stake_pool.end_time;
+    ctx.accounts.stake_pool.accrue(now);
+    let stake_entry = &mut ctx.accounts.stake_entry;
+    stake_entry.accrue(now, end_time);
+    stake_entry.stake_pool = ctx.accounts.stake_pool.key();
+    stake_entry.owner = ctx.accounts.authority.key();
+    stake_entry.bump = *ctx.bumps.get("stake_entry").unwrap();
+
+    anchor_spl::token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.user_stake_account.to_account_info(),
+                to: ctx.accounts.stake_vault.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    stake_entry.amount = stake_entry.amount.checked_add(amount).unwrap();
+    ctx.accounts.stake_pool.total_staked = ctx
+        .accounts
+        .stake_pool
+        .total_staked
+        .checked_add(amount)
+        .unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct Stake<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_STAKE_POOL_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [
+            SO_STAKE_ENTRY_SEED,
+            &stake_pool.key().to_bytes(),
+            &authority.key().to_bytes()
+        ],
+        bump,
+        space = StakeEntry::SPACE
+    )]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+
+    #[account(mut,
+        seeds = [SO_STAKE_VAULT_SEED, &stake_pool.key().to_bytes()],
+        bump = stake_pool.stake_vault_bump
+    )]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the staked tokens are coming from.
+    #[account(mut)]
+    pub user_stake_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Stake<'info> {
+    pub fn validate_accounts(&self, _strike: u64, amount: u64) -> Result<()> {
+        // Stake only earns during the subscription period.
+        check_not_expired!(self.stake_pool.end_time);
+
+        require!(amount > 0, SOErrorCode::InvalidConfig);
+
+        Ok(())
+    }
+}
+
+// Principal can be withdrawn at any time, even after the State is closed.
+// Stake seconds already earned are kept.
+pub fn unstake(ctx: Context<Unstake>, _strike: u64, amount: u64) -> Result<()> {
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+
+    let end_time: u64 = ctx.accounts.stake_pool.end_time;
+    ctx.accounts.stake_pool.accrue(now);
+    ctx.accounts.stake_entry.accrue(now, end_time);
+
+    let stake_pool_key: Pubkey = ctx.accounts.stake_pool.key();
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            anchor_spl::token::Transfer {
+                from: ctx.accounts.stake_vault.to_account_info(),
+                to: ctx.accounts.user_stake_account.to_account_info(),
+                authority: ctx.accounts.stake_vault.to_account_info(),
+            },
+            &[&[
+                SO_STAKE_VAULT_SEED,
+                &stake_pool_key.to_bytes(),
+                &[ctx.accounts.stake_pool.stake_vault_bump],
+            ]],
+        ),
+        amount,
+    )?;
+
+    ctx.accounts.stake_entry.amount = ctx.accounts.stake_entry.amount.checked_sub(amount).unwrap();
+    ctx.accounts.stake_pool.total_staked = ctx
+        .accounts
+        .stake_pool
+        .total_staked
+        .checked_sub(amount)
+        .unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct Unstake<'info> {
+    pub authority: Signer<'info>,
+
+    /// Derived from the stored State rather than the State account, since
+    /// withdraw_all closes the State after expiration.
+    #[account(mut,
+        seeds = [SO_STAKE_POOL_SEED, &stake_pool.state.to_bytes(), &strike.to_be_bytes()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+
+    #[account(mut,
+        seeds = [
+            SO_STAKE_ENTRY_SEED,
+            &stake_pool.key().to_bytes(),
+            &authority.key().to_bytes()
+        ],
+        bump = stake_entry.bump
+    )]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+
+    #[account(mut,
+        seeds = [SO_STAKE_VAULT_SEED, &stake_pool.key().to_bytes()],
+        bump = stake_pool.stake_vault_bump
+    )]
+    pub stake_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the staked tokens are going.
+    #[account(mut)]
+    pub user_stake_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> Unstake<'info> {
+    pub fn validate_accounts(&self, _strike: u64, amount: u64) -> Result<()> {
+        require!(
+            amount > 0 && amount <= self.stake_entry.amount,
+            SOErrorCode::NotEnoughTokens
+        );
+
+        Ok(())
+    }
+}
+
+pub fn claim_staking_options(ctx: Context<ClaimStakingOptions>, strike: u64) -> Result<()> {
+    check_mint!(ctx, strike, bump);
+
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let end_time: u64 = ctx.accounts.stake_pool.end_time;
+    ctx.accounts.stake_pool.accrue(now);
+    ctx.accounts.stake_entry.accrue(now, end_time);
+
+    let lots: u64 = ctx
+        .accounts
+        .stake_entry
+        .earned_lots(&ctx.accounts.stake_pool);
+    require!(lots > 0, SOErrorCode::NothingToClaim);
+
+    mint_options(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.option_mint.to_account_info(),
+        ctx.accounts.user_so_account.to_account_info(),
+        ctx.accounts.state.key(),
+        strike,
+        bump,
+        &mut ctx.accounts.strike_state,
+        lots,
+    )?;
+
+    ctx.accounts.stake_entry.claimed = true;
+    ctx.accounts.stake_pool.stake_seconds_claimed = ctx
+        .accounts
+        .stake_pool
+        .stake_seconds_claimed
+        .checked_add(ctx.accounts.stake_entry.stake_seconds)
+        .unwrap();
+    ctx.accounts.stake_pool.lots_claimed = ctx
+        .accounts
+        .stake_pool
+        .lots_claimed
+        .checked_add(lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        OptionsIssued {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots: lots,
+            recipient: ctx.accounts.user_so_account.key(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct ClaimStakingOptions<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_STAKE_POOL_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+
+    #[account(mut,
+        seeds = [
+            SO_STAKE_ENTRY_SEED,
+            &stake_pool.key().to_bytes(),
+            &authority.key().to_bytes()
+        ],
+        bump = stake_entry.bump
+    )]
+    pub stake_entry: Box<Account<'info, StakeEntry>>,
+
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the options will be sent.
+    #[account(mut)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> ClaimStakingOptions<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        // Shares are only final once stake stops accruing.
+        let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+        require!(
+            now > self.stake_pool.end_time,
+            SOErrorCode::NotYetExercisable
+        );
+
+        // Verify expiration
+        check_not_expired!(self.state.option_expiration);
+
+        require!(!self.stake_entry.claimed, SOErrorCode::AlreadyClaimed);
+        require!(!self.stake_pool.closed, SOErrorCode::StakePoolClosed);
+
+        Ok(())
+    }
+}
+
+// Returns collateral for lots nobody claimed. Allowed once every stake has
+// claimed, or after STAKE_POOL_CLAIM_GRACE_SEC for stakes too small to earn a
+// lot or never claimed. The pool is kept so stakers can still unstake.
+pub fn close_stake_pool(ctx: Context<CloseStakePool>, strike: u64) -> Result<()> {
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    ctx.accounts.stake_pool.accrue(now);
+
+    let stake_pool = &ctx.accounts.stake_pool;
+    require!(
+        stake_pool.stake_seconds_claimed == stake_pool.stake_seconds
+            || now > stake_pool.end_time + STAKE_POOL_CLAIM_GRACE_SEC,
+        SOErrorCode::ClaimsOutstanding
+    );
+
+    let unclaimed_lots: u64 = stake_pool
+        .total_lots
+        .checked_sub(stake_pool.lots_claimed)
+        .unwrap();
+    ctx.accounts
+        .state
+        .release_collateral(unclaimed_lots, strike);
+    ctx.accounts.stake_pool.closed = true;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CloseStakePool<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [SO_STAKE_POOL_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = stake_pool.bump
+    )]
+    pub stake_pool: Box<Account<'info, StakePool>>,
+}
+
+impl<'info> CloseStakePool<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Shares are only final once stake stops accruing.
+        let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+        require!(
+            now > self.stake_pool.end_time,
+            SOErrorCode::NotYetExercisable
+        );
+
+        check_not_expired!(self.state.option_expiration);
+
+        require!(!self.stake_pool.closed, SOErrorCode::StakePoolClosed);
+
+        Ok(())
+    }
+}
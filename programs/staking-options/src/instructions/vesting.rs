@@ -0,0 +1,287 @@
+use anchor_spl::token::{Mint, Token, TokenAccount};
+
+pub use crate::common::*;
+pub use crate::*;
+
+pub fn create_grant(
+    ctx: Context<CreateGrant>,
+    strike: u64,
+    total_lots: u64,
+    start: u64,
+    cliff: u64,
+    end: u64,
+) -> Result<()> {
+    let grant = &mut ctx.accounts.vesting_grant;
+    grant.state = ctx.accounts.state.key();
+    grant.strike = strike;
+    grant.recipient = ctx.accounts.recipient.key();
+    grant.bump = *ctx.bumps.get("vesting_grant").unwrap();
+    grant.total_lots = total_lots;
+    grant.claimed_lots = 0;
+    grant.start = start;
+    grant.cliff = cliff;
+    grant.end = end;
+
+    ctx.accounts
+        .state
+        .reserve_collateral(&mut ctx.accounts.strike_state, total_lots)?;
+
+    ctx.accounts.strike_state.lots_granted = ctx
+        .accounts
+        .strike_state
+        .lots_granted
+        .checked_add(total_lots)
+        .unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct CreateGrant<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    /// The strike needs to exist before granting options on it.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// CHECK: Only used as the owner of the grant.
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            SO_GRANT_SEED,
+            &state.key().to_bytes(),
+            &strike.to_be_bytes(),
+            &recipient.key().to_bytes()
+        ],
+        bump,
+        space = VestingGrant::SPACE
+    )]
+    pub vesting_grant: Box<Account<'info, VestingGrant>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateGrant<'info> {
+    pub fn validate_accounts(
+        &self,
+        _strike: u64,
+        total_lots: u64,
+        start: u64,
+        cliff: u64,
+        end: u64,
+    ) -> Result<()> {
+        require!(
+            self.state.is_issuer(self.authority.key()),
+            SOErrorCode::IncorrectAuthority
+        );
+
+        // Verify subscription period
+        check_not_expired!(self.state.subscription_period_end);
+
+        require!(
+            total_lots > 0 && start <= cliff && cliff <= end && end <= self.state.option_expiration,
+            SOErrorCode::InvalidConfig
+        );
+
+        Ok(())
+    }
+}
+
+pub fn claim_vested(ctx: Context<ClaimVested>, strike: u64) -> Result<()> {
+    check_mint!(ctx, strike, bump);
+
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let amount_lots: u64 = ctx
+        .accounts
+        .vesting_grant
+        .vested_lots(now)
+        .checked_sub(ctx.accounts.vesting_grant.claimed_lots)
+        .unwrap();
+    require!(amount_lots > 0, SOErrorCode::NothingToClaim);
+
+    mint_options(
+        ctx.accounts.token_program.to_account_info(),
+        ctx.accounts.option_mint.to_account_info(),
+        ctx.accounts.user_so_account.to_account_info(),
+        ctx.accounts.state.key(),
+        strike,
+        bump,
+        &mut ctx.accounts.strike_state,
+        amount_lots,
+    )?;
+
+    ctx.accounts.vesting_grant.claimed_lots = ctx
+        .accounts
+        .vesting_grant
+        .claimed_lots
+        .checked_add(amount_lots)
+        .unwrap();
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        OptionsIssued {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            strike,
+            amount_lots,
+            recipient: ctx.accounts.user_so_account.key(),
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct ClaimVested<'info> {
+    /// Must be the recipient of the grant.
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [
+            SO_GRANT_SEED,
+            &state.key().to_bytes(),
+            &strike.to_be_bytes(),
+            &authority.key().to_bytes()
+        ],
+        bump = vesting_grant.bump
+    )]
+    pub vesting_grant: Box<Account<'info, VestingGrant>>,
+
+    #[account(mut)]
+    pub option_mint: Box<Account<'info, Mint>>,
+
+    /// Accounting for this strike.
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+
+    /// Where the options will be sent.
+    #[account(mut)]
+    pub user_so_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> ClaimVested<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.vesting_grant.recipient,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        Ok(())
+    }
+}
+
+// Stops vesting. Lots that already vested can still be claimed by the
+// recipient, the collateral for the rest is returned to options_available.
+pub fn revoke_grant(ctx: Context<RevokeGrant>, strike: u64) -> Result<()> {
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+    let grant = &mut ctx.accounts.vesting_grant;
+    let vested_lots: u64 = grant.vested_lots(now);
+    let unvested_lots: u64 = grant.total_lots.checked_sub(vested_lots).unwrap();
+
+    // Collapse the schedule so everything left is vested as of now.
+    grant.total_lots = vested_lots;
+    grant.start = std::cmp::min(grant.start, now);
+    grant.cliff = std::cmp::min(grant.cliff, now);
+    grant.end = std::cmp::min(grant.end, now);
+
+    ctx.accounts.state.release_collateral(unvested_lots, strike);
+
+    ctx.accounts.strike_state.lots_granted = ctx
+        .accounts
+        .strike_state
+        .lots_granted
+        .checked_sub(unvested_lots)
+        .unwrap();
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(strike: u64)]
+pub struct RevokeGrant<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Box<Account<'info, State>>,
+
+    #[account(mut,
+        seeds = [
+            SO_GRANT_SEED,
+            &state.key().to_bytes(),
+            &strike.to_be_bytes(),
+            &vesting_grant.recipient.to_bytes()
+        ],
+        bump = vesting_grant.bump
+    )]
+    pub vesting_grant: Box<Account<'info, VestingGrant>>,
+
+    #[account(mut,
+        seeds = [SO_STRIKE_SEED, &state.key().to_bytes(), &strike.to_be_bytes()],
+        bump = strike_state.bump
+    )]
+    pub strike_state: Box<Account<'info, StrikeState>>,
+}
+
+impl<'info> RevokeGrant<'info> {
+    pub fn validate_accounts(&self, _strike: u64) -> Result<()> {
+        require_keys_eq!(
+            self.authority.key(),
+            self.state.authority,
+            SOErrorCode::IncorrectAuthority
+        );
+
+        check_not_expired!(self.state.option_expiration);
+
+        Ok(())
+    }
+}
@@ -20,28 +20,39 @@ pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
         &[ctx.accounts.state.vault_bump],
     ]];
 
-    if now > ctx.accounts.state.option_expiration {
-        // Send base tokens from the vault.
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer,
-                seeds,
-            ),
-            ctx.accounts.base_vault.amount,
-        )?;
+    let expired = now > ctx.accounts.state.option_expiration;
+    // Everything once expired, otherwise only the base not backing issued options.
+    let base_withdrawn: u64 = if expired {
+        ctx.accounts.base_vault.amount
     } else {
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                transfer,
-                seeds,
-            ),
-            ctx.accounts.state.options_available,
-        )?;
+        ctx.accounts.state.options_available
+    };
+
+    // Send base tokens from the vault.
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer,
+            seeds,
+        ),
+        base_withdrawn,
+    )?;
+    if !expired {
         ctx.accounts.state.options_available = 0;
     }
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Withdrawn {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            base_withdrawn,
+            quote_withdrawn: 0,
+            fee: 0,
+        },
+    )?;
+
     Ok(())
 }
 
@@ -75,6 +86,11 @@ pub struct Withdraw<'info> {
 
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> Withdraw<'info> {
@@ -105,16 +121,6 @@ pub fn withdraw_all(ctx: Context<WithdrawAll>) -> Result<()> {
         &[ctx.accounts.state.vault_bump],
     ]];
 
-    let quote_transfer = anchor_spl::token::Transfer {
-        from: ctx.accounts.quote_vault.to_account_info(),
-        to: ctx.accounts.quote_account.to_account_info(),
-        authority: ctx.accounts.quote_vault.to_account_info(),
-    };
-    let quote_fee_transfer = anchor_spl::token::Transfer {
-        from: ctx.accounts.quote_vault.to_account_info(),
-        to: ctx.accounts.fee_quote_account.to_account_info(),
-        authority: ctx.accounts.quote_vault.to_account_info(),
-    };
     let quote_seeds: &[&[&[u8]]] = &[&[
         SO_REVERSE_VAULT_SEED,
         &ctx.accounts.state.so_name.as_bytes(),
@@ -122,49 +128,47 @@ pub fn withdraw_all(ctx: Context<WithdrawAll>) -> Result<()> {
         &[ctx.accounts.state.quote_vault_bump],
     ]];
 
-    if now > ctx.accounts.state.option_expiration {
-        // Send base tokens from the vault.
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                base_transfer,
-                base_seeds,
-            ),
-            ctx.accounts.base_vault.amount,
-        )?;
-
-        let total_quote_tokens = ctx.accounts.quote_vault.amount;
-        let fee: u64 = if is_fee_exempt(ctx.accounts.quote_account.owner.key()) {
-            0
-        } else {
-            let fee_bps = get_fee_bps(
-                ctx.accounts.state.base_mint,
-                ctx.accounts.state.quote_mint,
-                ctx.accounts.state.so_name.clone(),
-            );
-
-            total_quote_tokens
-                .checked_mul(fee_bps)
-                .unwrap()
-                .checked_div(10_000)
-                .unwrap()
-        };
+    let expired = now > ctx.accounts.state.option_expiration;
+    let base_withdrawn: u64 = if expired {
+        ctx.accounts.base_vault.amount
+    } else {
+        ctx.accounts.state.options_available
+    };
+    // Dont withdraw the quote tokens before expiration since there are still
+    // reverse options exercisable for them.
+    let total_quote_tokens: u64 = if expired {
+        ctx.accounts.quote_vault.amount
+    } else {
+        0
+    };
+    let fee = calculate_fee(
+        &ctx.accounts.fee_schedule,
+        &ctx.accounts.state,
+        ctx.accounts.quote_account.owner.key(),
+        total_quote_tokens,
+    );
+    let quote_withdrawn = total_quote_tokens.checked_sub(fee).unwrap();
+
+    // Send base tokens from the vault.
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            base_transfer,
+            base_seeds,
+        ),
+        base_withdrawn,
+    )?;
+
+    if expired {
         // Send quote tokens from the vault.
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                quote_transfer,
-                quote_seeds,
-            ),
-            total_quote_tokens.checked_sub(fee).unwrap(),
-        )?;
-
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                quote_fee_transfer,
-                quote_seeds,
-            ),
+        transfer_payment_with_fee(
+            ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.quote_vault.to_account_info(),
+            ctx.accounts.quote_account.to_account_info(),
+            ctx.accounts.fee_quote_account.to_account_info(),
+            ctx.accounts.quote_vault.to_account_info(),
+            quote_seeds,
+            total_quote_tokens,
             fee,
         )?;
 
@@ -173,20 +177,21 @@ pub fn withdraw_all(ctx: Context<WithdrawAll>) -> Result<()> {
             .state
             .close(ctx.accounts.authority.to_account_info())?;
     } else {
-        anchor_spl::token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                base_transfer,
-                base_seeds,
-            ),
-            ctx.accounts.state.options_available,
-        )?;
         ctx.accounts.state.options_available = 0;
-
-        // Dont withdraw the quote tokens since there are still reverse options
-        // exercisable for them.
     }
 
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Withdrawn {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            base_withdrawn,
+            quote_withdrawn,
+            fee,
+        },
+    )?;
+
     Ok(())
 }
 
@@ -232,8 +237,20 @@ pub struct WithdrawAll<'info> {
     #[account(mut)]
     pub fee_quote_account: Box<Account<'info, TokenAccount>>,
 
+    /// Determines the fee charged on the quote tokens.
+    #[account(
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump
+    )]
+    pub fee_schedule: Box<Account<'info, FeeSchedule>>,
+
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
 }
 
 impl<'info> WithdrawAll<'info> {
@@ -253,3 +270,108 @@ impl<'info> WithdrawAll<'info> {
         Ok(())
     }
 }
+
+pub fn withdraw_put(ctx: Context<WithdrawPut>) -> Result<()> {
+    // Allow partial withdraw after the subscription period end.
+    let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+
+    let transfer = anchor_spl::token::Transfer {
+        from: ctx.accounts.put_vault.to_account_info(),
+        to: ctx.accounts.quote_account.to_account_info(),
+        authority: ctx.accounts.put_vault.to_account_info(),
+    };
+    let seeds: &[&[&[u8]]] = &[&[
+        SO_PUT_VAULT_SEED,
+        &ctx.accounts.state.so_name.as_bytes(),
+        &ctx.accounts.state.base_mint.key().to_bytes(),
+        &[ctx.accounts.state.put_vault_bump],
+    ]];
+
+    let expired = now > ctx.accounts.state.option_expiration;
+    // All remaining quote tokens once expired, otherwise only the quote tokens
+    // not backing any issued puts.
+    let quote_withdrawn: u64 = if expired {
+        ctx.accounts.put_vault.amount
+    } else {
+        ctx.accounts.state.options_available
+    };
+
+    anchor_spl::token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer,
+            seeds,
+        ),
+        quote_withdrawn,
+    )?;
+    if !expired {
+        ctx.accounts.state.options_available = 0;
+    }
+
+    emit_event(
+        &ctx.accounts.event_authority,
+        &ctx.accounts.program,
+        Withdrawn {
+            state: ctx.accounts.state.key(),
+            authority: ctx.accounts.authority.key(),
+            base_withdrawn: 0,
+            quote_withdrawn,
+            fee: 0,
+        },
+    )?;
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction()]
+pub struct WithdrawPut<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// State holding all the data for the stake that the staker wants to do.
+    #[account(mut,
+        seeds = [
+            SO_CONFIG_SEED,
+            state.so_name.as_bytes(),
+            &state.base_mint.key().to_bytes()
+        ],
+        bump = state.state_bump
+    )]
+    pub state: Account<'info, State>,
+
+    /// The quote tokens backing the puts.
+    #[account(mut,
+        seeds = [SO_PUT_VAULT_SEED, state.so_name.as_bytes(), &state.base_mint.key().to_bytes()],
+        bump = state.put_vault_bump,
+    )]
+    pub put_vault: Box<Account<'info, TokenAccount>>,
+
+    /// Where the tokens are getting returned to
+    #[account(mut)]
+    pub quote_account: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// CHECK: Checked in emit_event. Optional, and last, so that clients
+    /// from before events keep working.
+    pub event_authority: Option<UncheckedAccount<'info>>,
+    pub program: Option<Program<'info, StakingOptions>>,
+}
+
+impl<'info> WithdrawPut<'info> {
+    pub fn validate_accounts(&self) -> Result<()> {
+        // Verify the authority to withdraw against the state authority.
+        require_keys_eq!(self.authority.key(), self.state.authority);
+
+        require!(
+            self.state.option_type == OptionType::Put,
+            SOErrorCode::WrongOptionType
+        );
+
+        // Verify that subscription period has ended.
+        check_expired!(self.state.subscription_period_end);
+
+        Ok(())
+    }
+}
@@ -5,11 +5,19 @@ mod macros;
 
 mod common;
 mod errors;
+mod events;
+mod fees;
 mod instructions;
+mod merkle;
+mod oracle;
 
 pub use crate::common::*;
 pub use crate::errors::SOErrorCode;
+pub use crate::events::*;
+pub use crate::fees::*;
 pub use crate::instructions::*;
+pub use crate::merkle::*;
+pub use crate::oracle::*;
 
 #[cfg(not(feature = "no-entrypoint"))]
 solana_security_txt::security_txt! {
@@ -34,6 +42,11 @@ pub mod staking_options {
         add_tokens::add_tokens(ctx, num_tokens_to_add)
     }
 
+    #[access_control(ctx.accounts.validate_accounts(num_tokens_to_add))]
+    pub fn add_tokens_put(ctx: Context<AddTokensPut>, num_tokens_to_add: u64) -> Result<()> {
+        add_tokens::add_tokens_put(ctx, num_tokens_to_add)
+    }
+
     #[access_control(ctx.accounts.validate_accounts(option_expiration, subscription_period_end))]
     pub fn config(
         ctx: Context<Config>,
@@ -97,6 +110,14 @@ pub mod staking_options {
         )
     }
 
+    // Supersedes the previous configs, for both calls and puts. All options
+    // are in a single struct so new ones can be added without another
+    // instruction.
+    #[access_control(ctx.accounts.validate_accounts(&params))]
+    pub fn config_v4(ctx: Context<ConfigV4>, params: ConfigParams) -> Result<()> {
+        config::config_v4(ctx, params)
+    }
+
     #[access_control(ctx.accounts.validate_accounts(amount, strike))]
     pub fn exercise(ctx: Context<Exercise>, amount: u64, strike: u64) -> Result<()> {
         exercise::exercise(ctx, amount, strike)
@@ -116,6 +137,121 @@ pub mod staking_options {
         exercise::reverse_exercise(ctx, amount, strike)
     }
 
+    // Opts in to having options exercised by anyone shortly before
+    // expiration when they are in the money.
+    #[access_control(ctx.accounts.validate_accounts(strike, max_lots))]
+    pub fn register_auto_exercise(
+        ctx: Context<RegisterAutoExercise>,
+        strike: u64,
+        max_lots: u64,
+    ) -> Result<()> {
+        auto_exercise::register_auto_exercise(ctx, strike, max_lots)
+    }
+
+    // Only the owner can sign, which is checked by the PDA seeds.
+    pub fn unregister_auto_exercise(
+        ctx: Context<UnregisterAutoExercise>,
+        strike: u64,
+    ) -> Result<()> {
+        auto_exercise::unregister_auto_exercise(ctx, strike)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn crank_auto_exercise(ctx: Context<CrankAutoExercise>, strike: u64) -> Result<()> {
+        auto_exercise::crank_auto_exercise(ctx, strike)
+    }
+
+    // Delivers the base first and calls a whitelisted swap program, so the
+    // payment can come from selling the base in the same transaction.
+    #[access_control(ctx.accounts.validate_accounts(amount, strike))]
+    pub fn flash_exercise<'info>(
+        ctx: Context<'_, '_, '_, 'info, FlashExercise<'info>>,
+        amount: u64,
+        strike: u64,
+        swap_data: Vec<u8>,
+    ) -> Result<()> {
+        flash_exercise::flash_exercise(ctx, amount, strike, swap_data)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(program))]
+    pub fn add_swap_program(ctx: Context<AddSwapProgram>, program: Pubkey) -> Result<()> {
+        flash_exercise::add_swap_program(ctx, program)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts())]
+    pub fn remove_swap_program(ctx: Context<RemoveSwapProgram>) -> Result<()> {
+        flash_exercise::remove_swap_program(ctx)
+    }
+
+    // Pays the strike and fee with base tokens withheld from the exercise, so
+    // the holder does not need any quote.
+    #[access_control(ctx.accounts.validate_accounts(amount, strike))]
+    pub fn net_exercise(ctx: Context<NetExercise>, amount: u64, strike: u64) -> Result<()> {
+        exercise::net_exercise(ctx, amount, strike)
+    }
+
+    // Pays the in the money value in base tokens instead of requiring the
+    // strike payment.
+    #[access_control(ctx.accounts.validate_accounts(amount, strike))]
+    pub fn exercise_cash(ctx: Context<ExerciseCash>, amount: u64, strike: u64) -> Result<()> {
+        exercise::exercise_cash(ctx, amount, strike)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(amount, strike))]
+    pub fn exercise_put(ctx: Context<ExercisePut>, amount: u64, strike: u64) -> Result<()> {
+        exercise::exercise_put(ctx, amount, strike)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(&params))]
+    pub fn init_fee_schedule(
+        ctx: Context<InitFeeSchedule>,
+        params: FeeScheduleParams,
+    ) -> Result<()> {
+        fee_schedule::init_fee_schedule(ctx, params)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(&params))]
+    pub fn update_fee_schedule(
+        ctx: Context<UpdateFeeSchedule>,
+        params: FeeScheduleParams,
+    ) -> Result<()> {
+        fee_schedule::update_fee_schedule(ctx, params)
+    }
+
+    pub fn read_fee_bps(
+        ctx: Context<ReadFeeBps>,
+        base_mint: Pubkey,
+        quote_mint: Pubkey,
+        deal_type: DealType,
+    ) -> Result<u64> {
+        fee_schedule::read_fee_bps(ctx, base_mint, quote_mint, deal_type)
+    }
+
+    // Quotes do not modify anything, so they can be simulated to read what an
+    // instruction would cost from the return data.
+    #[access_control(ctx.accounts.validate_accounts(amount_lots, strike, exerciser))]
+    pub fn quote_exercise(
+        ctx: Context<QuoteExercise>,
+        amount_lots: u64,
+        strike: u64,
+        exerciser: Pubkey,
+    ) -> Result<ExerciseQuote> {
+        quote::quote_exercise(ctx, amount_lots, strike, exerciser)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(amount_lots, strike))]
+    pub fn quote_reverse_exercise(
+        ctx: Context<QuoteReverseExercise>,
+        amount_lots: u64,
+        strike: u64,
+    ) -> Result<ExerciseQuote> {
+        quote::quote_reverse_exercise(ctx, amount_lots, strike)
+    }
+
+    pub fn quote_withdraw_all(ctx: Context<QuoteWithdrawAll>) -> Result<WithdrawAllQuote> {
+        quote::quote_withdraw_all(ctx)
+    }
+
     #[access_control(ctx.accounts.validate_accounts(strike))]
     pub fn init_strike(ctx: Context<InitStrike>, strike: u64) -> Result<()> {
         init_strike::init_strike(ctx, strike)
@@ -131,15 +267,229 @@ pub mod staking_options {
         init_strike::init_strike_reversible(ctx, strike)
     }
 
-    #[access_control(ctx.accounts.validate_accounts(amount))]
+    pub fn init_strike_state(ctx: Context<InitStrikeState>, strike: u64) -> Result<()> {
+        init_strike::init_strike_state(ctx, strike)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(amount, strike))]
     pub fn issue(ctx: Context<Issue>, amount: u64, strike: u64) -> Result<()> {
         issue::issue(ctx, amount, strike)
     }
 
+    // Sells options on a strike with a price that decays over time.
+    #[access_control(ctx.accounts.validate_accounts(strike, &params))]
+    pub fn create_auction(
+        ctx: Context<CreateAuction>,
+        strike: u64,
+        params: AuctionParams,
+    ) -> Result<()> {
+        auction::create_auction(ctx, strike, params)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike, amount_lots, max_price_per_lot))]
+    pub fn buy_from_auction(
+        ctx: Context<BuyFromAuction>,
+        strike: u64,
+        amount_lots: u64,
+        max_price_per_lot: u64,
+    ) -> Result<()> {
+        auction::buy_from_auction(ctx, strike, amount_lots, max_price_per_lot)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn close_auction(ctx: Context<CloseAuction>, strike: u64) -> Result<()> {
+        auction::close_auction(ctx, strike)
+    }
+
+    // Sells options on a strike at a fixed premium.
+    #[access_control(ctx.accounts.validate_accounts(strike, premium_per_lot, max_lots))]
+    pub fn create_shop(
+        ctx: Context<CreateShop>,
+        strike: u64,
+        premium_per_lot: u64,
+        max_lots: u64,
+    ) -> Result<()> {
+        shop::create_shop(ctx, strike, premium_per_lot, max_lots)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike, amount_lots, max_premium_per_lot))]
+    pub fn buy_from_shop(
+        ctx: Context<BuyFromShop>,
+        strike: u64,
+        amount_lots: u64,
+        max_premium_per_lot: u64,
+    ) -> Result<()> {
+        shop::buy_from_shop(ctx, strike, amount_lots, max_premium_per_lot)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike, premium_per_lot))]
+    pub fn set_shop_premium(
+        ctx: Context<SetShopPremium>,
+        strike: u64,
+        premium_per_lot: u64,
+    ) -> Result<()> {
+        shop::set_shop_premium(ctx, strike, premium_per_lot)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn close_shop(ctx: Context<CloseShop>, strike: u64) -> Result<()> {
+        shop::close_shop(ctx, strike)
+    }
+
+    // Stakers deposit during the subscription period and earn options on a
+    // strike pro-rata to their stake seconds.
+    #[access_control(ctx.accounts.validate_accounts(strike, total_lots))]
+    pub fn create_stake_pool(
+        ctx: Context<CreateStakePool>,
+        strike: u64,
+        total_lots: u64,
+    ) -> Result<()> {
+        stake_pool::create_stake_pool(ctx, strike, total_lots)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike, amount))]
+    pub fn stake(ctx: Context<Stake>, strike: u64, amount: u64) -> Result<()> {
+        stake_pool::stake(ctx, strike, amount)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike, amount))]
+    pub fn unstake(ctx: Context<Unstake>, strike: u64, amount: u64) -> Result<()> {
+        stake_pool::unstake(ctx, strike, amount)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn claim_staking_options(ctx: Context<ClaimStakingOptions>, strike: u64) -> Result<()> {
+        stake_pool::claim_staking_options(ctx, strike)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn close_stake_pool(ctx: Context<CloseStakePool>, strike: u64) -> Result<()> {
+        stake_pool::close_stake_pool(ctx, strike)
+    }
+
+    // Airdrops options on a strike to the leaves of a merkle tree.
+    #[access_control(ctx.accounts.validate_accounts(strike, root, num_leaves, total_lots))]
+    pub fn set_merkle_root(
+        ctx: Context<SetMerkleRoot>,
+        strike: u64,
+        root: [u8; 32],
+        num_leaves: u64,
+        total_lots: u64,
+    ) -> Result<()> {
+        merkle_distributor::set_merkle_root(ctx, strike, root, num_leaves, total_lots)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike, index, lots, &proof))]
+    pub fn claim(
+        ctx: Context<Claim>,
+        strike: u64,
+        index: u64,
+        lots: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        merkle_distributor::claim(ctx, strike, index, lots, proof)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn close_merkle_distributor(
+        ctx: Context<CloseMerkleDistributor>,
+        strike: u64,
+    ) -> Result<()> {
+        merkle_distributor::close_merkle_distributor(ctx, strike)
+    }
+
+    // Reserves options for a recipient that are minted as they vest.
+    #[access_control(ctx.accounts.validate_accounts(strike, total_lots, start, cliff, end))]
+    pub fn create_grant(
+        ctx: Context<CreateGrant>,
+        strike: u64,
+        total_lots: u64,
+        start: u64,
+        cliff: u64,
+        end: u64,
+    ) -> Result<()> {
+        vesting::create_grant(ctx, strike, total_lots, start, cliff, end)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn claim_vested(ctx: Context<ClaimVested>, strike: u64) -> Result<()> {
+        vesting::claim_vested(ctx, strike)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn revoke_grant(ctx: Context<RevokeGrant>, strike: u64) -> Result<()> {
+        vesting::revoke_grant(ctx, strike)
+    }
+
+    pub fn migrate_state(ctx: Context<MigrateState>, so_name: String) -> Result<()> {
+        migrate_state::migrate_state(ctx, so_name)
+    }
+
     pub fn name_token(ctx: Context<NameToken>, strike: u64) -> Result<()> {
         name_token::name_token(ctx, strike)
     }
 
+    #[access_control(ctx.accounts.validate_accounts())]
+    pub fn set_deal_type(ctx: Context<SetDealType>, deal_type: DealType) -> Result<()> {
+        set_deal_type::set_deal_type(ctx, deal_type)
+    }
+
+    // Sets the cliff and optional Bermudan windows for exercising.
+    #[access_control(ctx.accounts.validate_accounts(exercise_start, &exercise_windows))]
+    pub fn set_exercise_schedule(
+        ctx: Context<SetExerciseSchedule>,
+        exercise_start: u64,
+        exercise_windows: Vec<ExerciseWindow>,
+    ) -> Result<()> {
+        set_exercise_schedule::set_exercise_schedule(ctx, exercise_start, exercise_windows)
+    }
+
+    // Makes exercise of a strike depend on the oracle price crossing a level.
+    #[access_control(ctx.accounts.validate_accounts(barrier_kind, barrier_price))]
+    pub fn set_barrier(
+        ctx: Context<SetBarrier>,
+        strike: u64,
+        barrier_kind: BarrierKind,
+        barrier_price: u64,
+    ) -> Result<()> {
+        barrier::set_barrier(ctx, strike, barrier_kind, barrier_price)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(strike))]
+    pub fn trigger_barrier(ctx: Context<TriggerBarrier>, strike: u64) -> Result<()> {
+        barrier::trigger_barrier(ctx, strike)
+    }
+
+    // Limits the payoff of a strike. Capped strikes are only cash exercised.
+    #[access_control(ctx.accounts.validate_accounts(strike, cap))]
+    pub fn set_strike_cap(ctx: Context<SetStrikeCap>, strike: u64, cap: u64) -> Result<()> {
+        set_strike_cap::set_strike_cap(ctx, strike, cap)
+    }
+
+    // Makes the strike charged on exercise change over time.
+    #[access_control(ctx.accounts.validate_accounts(&breakpoints))]
+    pub fn set_strike_schedule(
+        ctx: Context<SetStrikeSchedule>,
+        strike: u64,
+        schedule_kind: StrikeScheduleKind,
+        breakpoints: Vec<StrikeBreakpoint>,
+    ) -> Result<()> {
+        set_strike_schedule::set_strike_schedule(ctx, strike, schedule_kind, breakpoints)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(haircut_bps))]
+    pub fn set_net_exercise_haircut(
+        ctx: Context<SetNetExerciseHaircut>,
+        haircut_bps: u16,
+    ) -> Result<()> {
+        set_net_exercise_haircut::set_net_exercise_haircut(ctx, haircut_bps)
+    }
+
+    #[access_control(ctx.accounts.validate_accounts(max_age_sec))]
+    pub fn set_oracle(ctx: Context<SetOracle>, max_age_sec: u64) -> Result<()> {
+        set_oracle::set_oracle(ctx, max_age_sec)
+    }
+
     #[access_control(ctx.accounts.validate_accounts())]
     pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
         withdraw::withdraw(ctx)
@@ -150,6 +500,11 @@ pub mod staking_options {
         withdraw::withdraw_all(ctx)
     }
 
+    #[access_control(ctx.accounts.validate_accounts())]
+    pub fn withdraw_put(ctx: Context<WithdrawPut>) -> Result<()> {
+        withdraw::withdraw_put(ctx)
+    }
+
     // Access control checked in the handler.
     pub fn modify_expiration(
         ctx: Context<ModifyExpiration>,
@@ -157,4 +512,9 @@ pub mod staking_options {
     ) -> Result<()> {
         modify_expiration::modify_expiration(ctx, new_expiration_unix_sec)
     }
+
+    // Only the self CPI from emit_event lands here.
+    pub fn fallback(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> Result<()> {
+        events::handle_event_instruction(accounts, data)
+    }
 }
@@ -16,6 +16,17 @@ macro_rules! check_expired {
     };
 }
 
+macro_rules! check_exercisable {
+    ($state:expr) => {
+        let now: u64 = Clock::get().unwrap().unix_timestamp as u64;
+        require!(now >= $state.exercise_start, SOErrorCode::NotYetExercisable);
+        require!(
+            $state.in_exercise_window(now),
+            SOErrorCode::OutsideExerciseWindow
+        );
+    };
+}
+
 // This check verifies that nobody made a fake SO State at a different address.
 macro_rules! check_mint {
     ($ctx:expr, $strike:expr, $bump:ident) => {
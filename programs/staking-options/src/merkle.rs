@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak::hashv;
+
+// Leaf committed to in the distributor root. The index is the position of the
+// leaf in the tree and the bit used to track the claim.
+pub fn merkle_leaf(index: u64, claimant: &Pubkey, lots: u64) -> [u8; 32] {
+    hashv(&[
+        &index.to_le_bytes(),
+        &claimant.to_bytes(),
+        &lots.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+// Pairs are sorted before hashing, so proofs do not need to say which side
+// each sibling is on.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed: [u8; 32] = leaf;
+    for node in proof.iter() {
+        computed = if computed <= *node {
+            hashv(&[&computed, node]).to_bytes()
+        } else {
+            hashv(&[node, &computed]).to_bytes()
+        };
+    }
+    computed == root
+}
@@ -0,0 +1,99 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey;
+use std::convert::TryInto;
+
+use crate::errors::SOErrorCode;
+
+// Layout of a Pyth v2 price account. Only the fields needed for the aggregate
+// price are read.
+pub const PYTH_PROGRAM_ID: Pubkey = pubkey!("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH");
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_PRICE_ACCOUNT_TYPE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+
+const MAGIC_OFFSET: usize = 0;
+const ACCOUNT_TYPE_OFFSET: usize = 8;
+const EXPONENT_OFFSET: usize = 20;
+const TIMESTAMP_OFFSET: usize = 96;
+const AGGREGATE_PRICE_OFFSET: usize = 208;
+const AGGREGATE_STATUS_OFFSET: usize = 224;
+const PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+pub struct OraclePrice {
+    // Price of one base token in quote tokens is price * 10^exponent.
+    pub price: i64,
+    pub exponent: i32,
+
+    // Seconds since unix epoch of the last aggregate update.
+    pub publish_time: i64,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i32(data: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+// Reads the aggregate price from a Pyth price account. Fails when the account
+// is not owned by Pyth, or the price is not trading, not positive, or older
+// than max_age_sec.
+pub fn load_oracle_price(oracle: &AccountInfo, max_age_sec: u64) -> Result<OraclePrice> {
+    require_keys_eq!(*oracle.owner, PYTH_PROGRAM_ID, SOErrorCode::InvalidOracle);
+    let data = oracle.try_borrow_data()?;
+    require!(
+        data.len() >= PRICE_ACCOUNT_MIN_LEN
+            && read_u32(&data, MAGIC_OFFSET) == PYTH_MAGIC
+            && read_u32(&data, ACCOUNT_TYPE_OFFSET) == PYTH_PRICE_ACCOUNT_TYPE,
+        SOErrorCode::InvalidOracle
+    );
+    require!(
+        read_u32(&data, AGGREGATE_STATUS_OFFSET) == PYTH_STATUS_TRADING,
+        SOErrorCode::InvalidOracle
+    );
+
+    let oracle_price = OraclePrice {
+        price: read_i64(&data, AGGREGATE_PRICE_OFFSET),
+        exponent: read_i32(&data, EXPONENT_OFFSET),
+        publish_time: read_i64(&data, TIMESTAMP_OFFSET),
+    };
+    require!(oracle_price.price > 0, SOErrorCode::InvalidOracle);
+
+    let now: i64 = Clock::get().unwrap().unix_timestamp;
+    require!(
+        (now.saturating_sub(oracle_price.publish_time) as u64) <= max_age_sec,
+        SOErrorCode::StaleOracle
+    );
+
+    Ok(oracle_price)
+}
+
+// Converts an oracle price into quote atoms per lot, which is the same unit as
+// a strike.
+pub fn price_per_lot(
+    oracle_price: &OraclePrice,
+    lot_size: u64,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> u64 {
+    let exponent: i32 = oracle_price.exponent + quote_decimals as i32 - base_decimals as i32;
+    let value: u128 = (oracle_price.price as u128)
+        .checked_mul(lot_size as u128)
+        .unwrap();
+    let scaled: u128 = if exponent >= 0 {
+        value
+            .checked_mul(10_u128.checked_pow(exponent as u32).unwrap())
+            .unwrap()
+    } else {
+        value
+            .checked_div(10_u128.checked_pow((-exponent) as u32).unwrap())
+            .unwrap()
+    };
+
+    scaled.try_into().unwrap()
+}
@@ -1,7 +1,7 @@
 import assert from 'assert';
-import { PublicKey, Transaction } from '@solana/web3.js';
+import { Keypair, PublicKey, Transaction } from '@solana/web3.js';
 import {
-  AnchorProvider, Program, BN, workspace,
+  AnchorProvider, Program, BN, utils, workspace,
 } from '@coral-xyz/anchor';
 import { StakingOptions as SO } from '@dual-finance/staking-options';
 import {
@@ -9,7 +9,9 @@ import {
 } from '@project-serum/associated-token';
 import { Metaplex } from '@metaplex-foundation/js';
 import { getAccount } from '@solana/spl-token';
+import { keccak_256 as keccak256 } from 'js-sha3';
 import { StakingOptions } from '../target/types/staking_options';
+import { MockSwap } from '../target/types/mock_swap';
 import {
   DEFAULT_MINT_DECIMALS,
   createMint,
@@ -47,7 +49,16 @@ describe('staking-options', () => {
   let SO_NAME: string = 'SO_staking_options_SO';
   const OPTION_EXPIRATION_DELAY_SEC = 100;
 
-  async function configureSO() {
+  async function feeSchedulePda() {
+    const [feeSchedule] = await PublicKey.findProgramAddress(
+      [Buffer.from('fee-schedule')],
+      program.programId,
+    );
+    return feeSchedule;
+  }
+
+  // Params given in overrides replace the defaults passed to configV4.
+  async function configureSO(overrides = {}) {
     console.log('Configuring SO');
 
     subscriptionPeriodEnd = Math.floor(Date.now() / 1_000 + OPTION_EXPIRATION_DELAY_SEC / 2);
@@ -80,35 +91,115 @@ describe('staking-options', () => {
     state = await so.state(SO_NAME, baseMint);
     baseVault = await so.baseVault(SO_NAME, baseMint);
 
-    const instr = await so.createConfigInstruction(
-      optionExpiration,
-      subscriptionPeriodEnd,
-      new BN(numTokens),
-      new BN(LOT_SIZE),
-      SO_NAME,
-      provider.wallet.publicKey,
-      baseMint,
-      baseAccount,
-      quoteMint,
-      quoteAccount,
+    const [quoteVault] = await PublicKey.findProgramAddress(
+      [Buffer.from('so-reverse-vault'), Buffer.from(SO_NAME), baseMint.toBuffer()],
+      program.programId,
     );
+    const instr = await program.methods
+      .configV4({
+        optionExpiration: new BN(optionExpiration),
+        subscriptionPeriodEnd: new BN(subscriptionPeriodEnd),
+        numTokens: new BN(numTokens),
+        lotSize: new BN(LOT_SIZE),
+        soName: SO_NAME,
+        reversible: true,
+        exerciseStart: new BN(0),
+        optionDecimals: 0,
+        optionType: { call: {} },
+        ...overrides,
+      })
+      .accounts({
+        authority: provider.wallet.publicKey,
+        soAuthority: provider.wallet.publicKey,
+        issueAuthority: null,
+        state,
+        baseVault,
+        quoteVault,
+        putVault: null,
+        baseAccount,
+        quoteAccount,
+        baseMint,
+        quoteMint,
+      })
+      .instruction();
 
     const tx = new Transaction();
     tx.add(instr);
     await provider.sendAndConfirm(tx);
   }
 
+  async function strikeStatePda(strike: number) {
+    const [strikeState] = await PublicKey.findProgramAddress(
+      [
+        Buffer.from('so-strike'),
+        state.toBuffer(),
+        new BN(strike).toArrayLike(Buffer, 'be', 8),
+      ],
+      program.programId,
+    );
+    return strikeState;
+  }
+
+  async function eventAuthorityPda() {
+    const [eventAuthority] = await PublicKey.findProgramAddress(
+      [Buffer.from('__event_authority')],
+      program.programId,
+    );
+    return eventAuthority;
+  }
+
+  // Decodes the event carried by the self CPI of a confirmed transaction. The
+  // event is the instruction data after the 8 byte event tag.
+  async function selfCpiEvent(signature: string) {
+    const txResult = await provider.connection.getTransaction(signature, {
+      commitment: 'confirmed',
+    });
+    const eventIx = txResult.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .find((ix) => txResult.transaction.message.accountKeys[ix.programIdIndex]
+        .equals(program.programId));
+    const eventData = utils.bytes.bs58.decode(eventIx.data).slice(8);
+    return program.coder.events.decode(
+      utils.bytes.base64.encode(Buffer.from(eventData)),
+    );
+  }
+
   async function initStrike(strike: number) {
     console.log('Init Strike');
 
     optionMint = await so.soMint(strike, SO_NAME, baseMint);
 
-    const instr = await so.createInitStrikeReversibleInstruction(
-      new BN(strike),
-      SO_NAME,
-      provider.wallet.publicKey,
-      baseMint,
-    );
+    const instr = await program.methods
+      .initStrikeReversible(new BN(strike))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        payer: provider.wallet.publicKey,
+        state,
+        reverseOptionMint: await so.reverseSoMint(strike, SO_NAME, baseMint),
+        optionMint,
+        strikeState: await strikeStatePda(strike),
+      })
+      .instruction();
+
+    const tx = new Transaction();
+    tx.add(instr);
+    await provider.sendAndConfirm(tx);
+  }
+
+  async function initStrikeNotReversible(strike: number) {
+    console.log('Init Strike Not Reversible');
+
+    optionMint = await so.soMint(strike, SO_NAME, baseMint);
+
+    const instr = await program.methods
+      .initStrike(new BN(strike))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+        optionMint,
+        strikeState: await strikeStatePda(strike),
+      })
+      .instruction();
 
     const tx = new Transaction();
     tx.add(instr);
@@ -124,14 +215,16 @@ describe('staking-options', () => {
       provider.wallet.publicKey,
     );
 
-    const instr = await so.createIssueInstruction(
-      new BN(amount),
-      new BN(strike),
-      SO_NAME,
-      provider.wallet.publicKey,
-      baseMint,
-      userSoAccount,
-    );
+    const instr = await program.methods
+      .issue(new BN(amount), new BN(strike))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+        optionMint,
+        strikeState: await strikeStatePda(strike),
+        userSoAccount,
+      })
+      .instruction();
     const tx = new Transaction();
     tx.add(instr);
     await provider.sendAndConfirm(tx);
@@ -180,6 +273,49 @@ describe('staking-options', () => {
       provider.wallet.publicKey,
     );
 
+    const feeAccount = await createFeeAccount();
+
+    console.log('Creating exercise instruction');
+    const instr = await program.methods
+      .exercise(new BN(amount), new BN(STRIKE))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+        userSoAccount,
+        optionMint,
+        strikeState: await strikeStatePda(STRIKE),
+        userQuoteAccount,
+        projectQuoteAccount: quoteAccount,
+        feeQuoteAccount: feeAccount,
+        feeSchedule: await feeSchedulePda(),
+        baseVault,
+        userBaseAccount,
+      })
+      .instruction();
+    const tx = new Transaction();
+    tx.add(instr);
+    await provider.sendAndConfirm(tx);
+  }
+
+  async function withdraw(sleep = OPTION_EXPIRATION_DELAY_SEC) {
+    console.log('Withdrawing');
+    console.log(`Sleeping til options expire: ${Date.now() / 1_000}`);
+    await new Promise((r) => setTimeout(r, sleep * 1_000));
+    console.log(`Done sleeping: ${Date.now() / 1_000}`);
+
+    const instr = await so.createWithdrawInstruction(
+      SO_NAME,
+      provider.wallet.publicKey,
+      baseAccount,
+      quoteAccount,
+    );
+    const tx = new Transaction();
+    tx.add(instr);
+    await provider.sendAndConfirm(tx);
+  }
+
+  // The DAO owned account that receives fees in the quote token.
+  async function createFeeAccount() {
     const feeAccount = await SO.getFeeAccount(quoteMint);
 
     try {
@@ -197,34 +333,30 @@ describe('staking-options', () => {
       console.log(err);
       console.log('Fee account already exists');
     }
-
-    console.log('Creating exercise instruction');
-    const instr = await so.createExerciseInstruction(
-      new BN(amount),
-      new BN(STRIKE),
-      SO_NAME,
-      provider.wallet.publicKey,
-      userSoAccount,
-      userQuoteAccount,
-      userBaseAccount,
-    );
-    const tx = new Transaction();
-    tx.add(instr);
-    await provider.sendAndConfirm(tx);
+    return feeAccount;
   }
 
-  async function withdraw(sleep = OPTION_EXPIRATION_DELAY_SEC) {
-    console.log('Withdrawing');
-    console.log(`Sleeping til options expire: ${Date.now() / 1_000}`);
-    await new Promise((r) => setTimeout(r, sleep * 1_000));
-    console.log(`Done sleeping: ${Date.now() / 1_000}`);
+  // Closes the State once the options have expired.
+  async function withdrawAll() {
+    console.log('Withdrawing all');
 
-    const instr = await so.createWithdrawInstruction(
-      SO_NAME,
-      provider.wallet.publicKey,
-      baseAccount,
-      quoteAccount,
+    const [quoteVault] = await PublicKey.findProgramAddress(
+      [Buffer.from('so-reverse-vault'), Buffer.from(SO_NAME), baseMint.toBuffer()],
+      program.programId,
     );
+    const instr = await program.methods
+      .withdrawAll()
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+        baseVault,
+        baseAccount,
+        quoteVault,
+        quoteAccount,
+        feeQuoteAccount: await createFeeAccount(),
+        feeSchedule: await feeSchedulePda(),
+      })
+      .instruction();
     const tx = new Transaction();
     tx.add(instr);
     await provider.sendAndConfirm(tx);
@@ -267,12 +399,93 @@ describe('staking-options', () => {
     assert.equal(stateObj.strikes.length, 0);
     assert.equal(stateObj.lotSize, LOT_SIZE);
     assert.equal(stateObj.soName, SO_NAME);
+    assert.equal(stateObj.version, 6);
 
     // Verify the tokens are stored.
     const baseVaultAccount = await getAccount(provider.connection, baseVault);
     assert.equal(Number(baseVaultAccount.amount), numTokens);
   });
 
+  it('Config Legacy Success', async () => {
+    subscriptionPeriodEnd = Math.floor(Date.now() / 1_000 + OPTION_EXPIRATION_DELAY_SEC / 2);
+    optionExpiration = Math.floor(Date.now() / 1_000 + OPTION_EXPIRATION_DELAY_SEC);
+
+    baseMint = await createMint(provider, undefined);
+    baseAccount = await createTokenAccount(
+      provider,
+      baseMint,
+      provider.wallet.publicKey,
+    );
+    await mintToAccount(
+      provider,
+      baseMint,
+      baseAccount,
+      new BN(numTokens),
+      provider.wallet.publicKey,
+    );
+    state = await so.state(SO_NAME, baseMint);
+    baseVault = await so.baseVault(SO_NAME, baseMint);
+
+    const tx = new Transaction();
+    tx.add(await so.createConfigInstruction(
+      optionExpiration,
+      subscriptionPeriodEnd,
+      new BN(numTokens),
+      new BN(LOT_SIZE),
+      SO_NAME,
+      provider.wallet.publicKey,
+      baseMint,
+      baseAccount,
+      quoteMint,
+      quoteAccount,
+    ));
+    await provider.sendAndConfirm(tx);
+
+    const stateObj = await program.account.state.fetch(state);
+    assert.equal(stateObj.optionsAvailable.toNumber(), numTokens);
+    assert.equal(stateObj.lotSize, LOT_SIZE);
+    assert.equal(stateObj.version, 6);
+    assert.equal(stateObj.exerciseStart.toNumber(), 0);
+    assert.equal(stateObj.optionDecimals, 0);
+    assert.deepEqual(stateObj.optionType, { call: {} });
+
+    const baseVaultAccount = await getAccount(provider.connection, baseVault);
+    assert.equal(Number(baseVaultAccount.amount), numTokens);
+  });
+
+  it('Config V4 Success', async () => {
+    const exerciseStart = Math.floor(Date.now() / 1_000 + OPTION_EXPIRATION_DELAY_SEC / 4);
+    await configureSO({ exerciseStart: new BN(exerciseStart), optionDecimals: 2 });
+
+    const stateObj = await program.account.state.fetch(state);
+    assert.equal(stateObj.exerciseStart.toNumber(), exerciseStart);
+    assert.equal(stateObj.optionDecimals, 2);
+    // Reversible, so the quote vault was created.
+    assert.notEqual(stateObj.quoteVaultBump, 0);
+  });
+
+  it('Config V4 Fail Zero Lot Size', async () => {
+    let configured = false;
+    try {
+      await configureSO({ lotSize: new BN(0) });
+      configured = true;
+    } catch (err) {
+      console.log('Config failed as expected');
+    }
+    assert(!configured);
+  });
+
+  it('Config V4 Fail Option Decimals', async () => {
+    let configured = false;
+    try {
+      await configureSO({ optionDecimals: DEFAULT_MINT_DECIMALS + 1 });
+      configured = true;
+    } catch (err) {
+      console.log('Config failed as expected');
+    }
+    assert(!configured);
+  });
+
   it('InitStrike Success', async () => {
     await configureSO();
     await initStrike(STRIKE);
@@ -280,6 +493,13 @@ describe('staking-options', () => {
     // Verify the strike exists in the state.
     const stateObj = await program.account.state.fetch(state);
     assert.equal(stateObj.strikes[0].toNumber(), STRIKE);
+
+    const strikeStateObj = await program.account.strikeState.fetch(
+      await strikeStatePda(STRIKE),
+    );
+    assert.equal(strikeStateObj.state.toBase58(), state.toBase58());
+    assert.equal(strikeStateObj.strike.toNumber(), STRIKE);
+    assert.equal(strikeStateObj.lotsIssued.toNumber(), 0);
   });
 
   it('Issue Success', async () => {
@@ -289,6 +509,11 @@ describe('staking-options', () => {
 
     const userSoAccountAccount = await getAccount(provider.connection, userSoAccount);
     assert.equal(Number(userSoAccountAccount.amount), OPTIONS_AMOUNT / LOT_SIZE);
+
+    const strikeStateObj = await program.account.strikeState.fetch(
+      await strikeStatePda(STRIKE),
+    );
+    assert.equal(strikeStateObj.lotsIssued.toNumber(), OPTIONS_AMOUNT / LOT_SIZE);
   });
 
   it('AddTokens Success', async () => {
@@ -452,16 +677,27 @@ describe('staking-options', () => {
       // Wait for token accounts to all be initialized.
       await new Promise((r) => setTimeout(r, 5_000));
 
-      const reversibleExerciseInstr = await so.createExerciseReversibleInstruction(
-        new BN(OPTIONS_AMOUNT / LOT_SIZE),
-        new BN(STRIKE),
-        SO_NAME,
-        provider.wallet.publicKey,
-        userSoAccount,
-        userReverseSoAccount,
-        userQuoteAccount,
-        userBaseAccount,
+      const [quoteVault] = await PublicKey.findProgramAddress(
+        [Buffer.from('so-reverse-vault'), Buffer.from(SO_NAME), baseMint.toBuffer()],
+        program.programId,
       );
+      const strikeState = await strikeStatePda(STRIKE);
+      const reversibleExerciseInstr = await program.methods
+        .exerciseReversible(new BN(OPTIONS_AMOUNT / LOT_SIZE), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState,
+          userReverseSoAccount,
+          reverseOptionMint,
+          userQuoteAccount,
+          quoteVault,
+          baseVault,
+          userBaseAccount,
+        })
+        .instruction();
 
       const exerciseTx = new Transaction();
       exerciseTx.add(reversibleExerciseInstr);
@@ -469,16 +705,22 @@ describe('staking-options', () => {
 
       await new Promise((r) => setTimeout(r, 5_000));
 
-      const reverseExerciseInstr = await so.createReverseExerciseInstruction(
-        new BN(OPTIONS_AMOUNT / LOT_SIZE / 2),
-        new BN(STRIKE),
-        SO_NAME,
-        provider.wallet.publicKey,
-        userSoAccount,
-        userReverseSoAccount,
-        userQuoteAccount,
-        userBaseAccount,
-      );
+      const reverseExerciseInstr = await program.methods
+        .reverseExercise(new BN(OPTIONS_AMOUNT / LOT_SIZE / 2), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState,
+          userReverseSoAccount,
+          reverseOptionMint,
+          userQuoteAccount,
+          quoteVault,
+          baseVault,
+          userBaseAccount,
+        })
+        .instruction();
 
       const reverseTx = new Transaction();
       reverseTx.add(reverseExerciseInstr);
@@ -498,5 +740,1797 @@ describe('staking-options', () => {
     }
   });
 
+  it('Read Fee Bps', async () => {
+    const feeSchedule = await feeSchedulePda();
+    const usdc = new PublicKey('EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v');
+    const usdt = new PublicKey('Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB');
+    const wsol = new PublicKey('So11111111111111111111111111111111111111112');
+
+    const stableFeeBps = await program.methods
+      .readFeeBps(usdc, usdt, { standard: {} })
+      .accounts({ feeSchedule })
+      .view();
+    assert.equal(stableFeeBps.toNumber(), 5);
+
+    const majorFeeBps = await program.methods
+      .readFeeBps(wsol, usdc, { standard: {} })
+      .accounts({ feeSchedule })
+      .view();
+    assert.equal(majorFeeBps.toNumber(), 10);
+
+    const defaultFeeBps = await program.methods
+      .readFeeBps(wsol, baseMint, { standard: {} })
+      .accounts({ feeSchedule })
+      .view();
+    assert.equal(defaultFeeBps.toNumber(), 350);
+
+    const otcFeeBps = await program.methods
+      .readFeeBps(wsol, baseMint, { otc: {} })
+      .accounts({ feeSchedule })
+      .view();
+    assert.equal(otcFeeBps.toNumber(), 10);
+  });
+
+  it('Migrate Legacy State', async () => {
+    // Loaded from tests/fixtures, written by the original config before the
+    // State had a version.
+    const legacyAuthority = Keypair.fromSeed(new Uint8Array(32).fill(7));
+    const legacyBaseMint = new PublicKey('2B7ZQCQEYSrn71WMgevKuYWzf2v7a2ER7ZNReoWBSWwp');
+    const legacyQuoteMint = new PublicKey('7JACLATYAeUCvjkdb9S1oQ6Xag42aLZ8mPeBaWdRdXoj');
+    const legacyState = new PublicKey('EzqxbiNBpdS9jUFrNKMeTHbpY3wcFToms3CaGk47JBnW');
+    const [legacyQuoteVault, quoteVaultBump] = await PublicKey.findProgramAddress(
+      [Buffer.from('so-reverse-vault'), Buffer.from('LEGACY'), legacyBaseMint.toBuffer()],
+      program.programId,
+    );
+
+    const migrateTx = new Transaction();
+    migrateTx.add(await program.methods
+      .migrateState('LEGACY')
+      .accounts({
+        authority: legacyAuthority.publicKey,
+        payer: provider.wallet.publicKey,
+        state: legacyState,
+        quoteVault: legacyQuoteVault,
+        baseMint: legacyBaseMint,
+        quoteMint: legacyQuoteMint,
+      })
+      .instruction());
+    await provider.sendAndConfirm(migrateTx, [legacyAuthority]);
+
+    const stateAccount = await provider.connection.getAccountInfo(legacyState);
+    // Grown from the 1150 bytes of the original layout.
+    assert(stateAccount.data.length > 1150);
+
+    const stateObj = await program.account.state.fetch(legacyState);
+    assert.equal(stateObj.version, 6);
+    assert.equal(stateObj.soName, 'LEGACY');
+    assert.equal(stateObj.authority.toBase58(), legacyAuthority.publicKey.toBase58());
+    assert.equal(stateObj.issueAuthority.toBase58(), legacyAuthority.publicKey.toBase58());
+    assert.deepEqual(stateObj.strikes.map((strike) => strike.toNumber()), [100, 200, 300]);
+    assert.equal(stateObj.lotSize.toNumber(), 1_000);
+    assert.equal(stateObj.quoteVaultBump, quoteVaultBump);
+    assert.equal(stateObj.optionDecimals, 0);
+    assert.deepEqual(stateObj.optionType, { call: {} });
+    assert.equal(stateObj.exerciseWindows.length, 0);
+    // Has strikes, so options may have been issued.
+    assert(stateObj.optionsCommitted);
+
+    const quoteVaultAccount = await getAccount(provider.connection, legacyQuoteVault);
+    assert.equal(quoteVaultAccount.mint.toBase58(), legacyQuoteMint.toBase58());
+  });
+
+  it('E2E Put', async () => {
+    try {
+      subscriptionPeriodEnd = Math.floor(Date.now() / 1_000 + OPTION_EXPIRATION_DELAY_SEC / 2);
+      optionExpiration = Math.floor(Date.now() / 1_000 + OPTION_EXPIRATION_DELAY_SEC);
+
+      baseMint = await createMint(provider, undefined);
+      baseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+      if (!quoteMint) {
+        quoteMint = await createMint(provider, undefined);
+        quoteAccount = await createTokenAccount(
+          provider,
+          quoteMint,
+          provider.wallet.publicKey,
+        );
+      }
+      const putCollateral = (OPTIONS_AMOUNT / LOT_SIZE) * STRIKE;
+      // Added after config and withdrawn before expiration since no puts are
+      // issued against it.
+      const addedCollateral = 5 * STRIKE;
+      await mintToAccount(
+        provider,
+        quoteMint,
+        quoteAccount,
+        new BN(putCollateral + addedCollateral),
+        provider.wallet.publicKey,
+      );
+
+      state = await so.state(SO_NAME, baseMint);
+      const [putVault] = await PublicKey.findProgramAddress(
+        [Buffer.from('so-put-vault'), Buffer.from(SO_NAME), baseMint.toBuffer()],
+        program.programId,
+      );
+      const configTx = new Transaction();
+      configTx.add(await program.methods
+        .configV4({
+          optionExpiration: new BN(optionExpiration),
+          subscriptionPeriodEnd: new BN(subscriptionPeriodEnd),
+          numTokens: new BN(putCollateral),
+          lotSize: new BN(LOT_SIZE),
+          soName: SO_NAME,
+          reversible: false,
+          exerciseStart: new BN(0),
+          optionDecimals: 0,
+          optionType: { put: {} },
+        })
+        .accounts({
+          authority: provider.wallet.publicKey,
+          soAuthority: provider.wallet.publicKey,
+          issueAuthority: null,
+          state,
+          baseVault: null,
+          quoteVault: null,
+          putVault,
+          quoteAccount,
+          baseAccount,
+          baseMint,
+          quoteMint,
+        })
+        .instruction());
+      await provider.sendAndConfirm(configTx);
+
+      const addTokensTx = new Transaction();
+      addTokensTx.add(await program.methods
+        .addTokensPut(new BN(addedCollateral))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          putVault,
+          quoteAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(addTokensTx);
+      assert.equal(
+        Number((await getAccount(provider.connection, putVault)).amount),
+        putCollateral + addedCollateral,
+      );
+
+      await initStrike(STRIKE);
+      await issue(OPTIONS_AMOUNT, STRIKE);
+      assert.equal(
+        (await program.account.state.fetch(state)).optionsAvailable.toNumber(),
+        addedCollateral,
+      );
+
+      userBaseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+      await mintToAccount(
+        provider,
+        baseMint,
+        userBaseAccount,
+        new BN(OPTIONS_AMOUNT),
+        provider.wallet.publicKey,
+      );
+      userQuoteAccount = await createTokenAccount(
+        provider,
+        quoteMint,
+        provider.wallet.publicKey,
+      );
+      const feeAccount = await SO.getFeeAccount(quoteMint);
+      // Half the puts, so some collateral is left at expiration.
+      const exercisedLots = OPTIONS_AMOUNT / LOT_SIZE / 2;
+      const exercisedCollateral = exercisedLots * STRIKE;
+
+      const exerciseTx = new Transaction();
+      exerciseTx.add(await program.methods
+        .exercisePut(new BN(exercisedLots), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          userBaseAccount,
+          projectBaseAccount: baseAccount,
+          putVault,
+          userQuoteAccount,
+          feeQuoteAccount: feeAccount,
+          feeSchedule: await feeSchedulePda(),
+        })
+        .instruction());
+      await provider.sendAndConfirm(exerciseTx);
+
+      // Base is delivered to the project and the strike less the default
+      // fee is paid to the holder.
+      assert.equal(
+        Number((await getAccount(provider.connection, baseAccount)).amount),
+        exercisedLots * LOT_SIZE,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, userQuoteAccount)).amount),
+        exercisedCollateral - (exercisedCollateral * 350) / 10_000,
+      );
+
+      async function withdrawPut() {
+        const withdrawTx = new Transaction();
+        withdrawTx.add(await program.methods
+          .withdrawPut()
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state,
+            putVault,
+            quoteAccount,
+          })
+          .instruction());
+        await provider.sendAndConfirm(withdrawTx);
+      }
+
+      // Before expiration only the collateral not backing issued puts.
+      await new Promise((r) => setTimeout(
+        r,
+        (subscriptionPeriodEnd + 2) * 1_000 - Date.now(),
+      ));
+      const quoteBefore = Number((await getAccount(provider.connection, quoteAccount)).amount);
+      await withdrawPut();
+      assert.equal(
+        Number((await getAccount(provider.connection, quoteAccount)).amount),
+        quoteBefore + addedCollateral,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, putVault)).amount),
+        putCollateral - exercisedCollateral,
+      );
+      assert.equal((await program.account.state.fetch(state)).optionsAvailable.toNumber(), 0);
+
+      // After expiration everything left in the vault.
+      await new Promise((r) => setTimeout(
+        r,
+        (optionExpiration + 2) * 1_000 - Date.now(),
+      ));
+      await withdrawPut();
+      assert.equal(
+        Number((await getAccount(provider.connection, quoteAccount)).amount),
+        quoteBefore + addedCollateral + putCollateral - exercisedCollateral,
+      );
+      assert.equal(Number((await getAccount(provider.connection, putVault)).amount), 0);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('E2E Cash Exercise', async () => {
+    try {
+      await configureSO();
+
+      // The fixture price is never updated, so allow any age.
+      const oracle = new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8');
+      const oracleTx = new Transaction();
+      oracleTx.add(await program.methods
+        .setOracle(new BN('18446744073709551615'))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          oracle,
+        })
+        .instruction());
+      await provider.sendAndConfirm(oracleTx);
+
+      await initStrike(STRIKE);
+      await issue(OPTIONS_AMOUNT, STRIKE);
+
+      userBaseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+      const feeBaseAccount = await SO.getFeeAccount(baseMint);
+      const ataTx = new Transaction();
+      ataTx.add(
+        await createAssociatedTokenAccount(
+          provider.wallet.publicKey,
+          new PublicKey('7Z36Efbt7a4nLiV7s5bY7J2e4TJ6V9JEKGccsy2od2bE'),
+          baseMint,
+        ),
+      );
+      await provider.sendAndConfirm(ataTx);
+
+      const amountLots = 1;
+      const exerciseTx = new Transaction();
+      exerciseTx.add(await program.methods
+        .exerciseCash(new BN(amountLots), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          oracle,
+          quoteMint,
+          baseVault,
+          userBaseAccount,
+          feeBaseAccount,
+          feeSchedule: await feeSchedulePda(),
+        })
+        .instruction());
+      await provider.sendAndConfirm(exerciseTx);
+
+      // Price per lot is 2 quote per base for a lot of LOT_SIZE base atoms.
+      const pricePerLot = 2 * LOT_SIZE;
+      const intrinsic = Math.floor((amountLots * LOT_SIZE * (pricePerLot - STRIKE)) / pricePerLot);
+      const fee = Math.floor((intrinsic * 350) / 10_000);
+      assert.equal(
+        Number((await getAccount(provider.connection, userBaseAccount)).amount),
+        intrinsic - fee,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, feeBaseAccount)).amount),
+        fee,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Set Oracle Fail', async () => {
+    await configureSO();
+
+    async function setOracle(oracle: PublicKey) {
+      const oracleTx = new Transaction();
+      oracleTx.add(await program.methods
+        .setOracle(new BN('18446744073709551615'))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          oracle,
+        })
+        .instruction());
+      await provider.sendAndConfirm(oracleTx);
+    }
+
+    // Accounts not owned by Pyth are rejected even with the right layout.
+    let setNotPyth = false;
+    try {
+      await setOracle(baseMint);
+      setNotPyth = true;
+    } catch (err) {
+      console.log('Set oracle failed as expected');
+    }
+    assert(!setNotPyth);
+
+    await setOracle(new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8'));
+    await initStrike(STRIKE);
+    await issue(OPTIONS_AMOUNT, STRIKE);
+
+    // The oracle is fixed once options are issued.
+    let setAfterIssue = false;
+    try {
+      await setOracle(new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8'));
+      setAfterIssue = true;
+    } catch (err) {
+      console.log('Set oracle failed as expected');
+    }
+    assert(!setAfterIssue);
+  });
+
+  it('Exercise Outside Window Fail', async () => {
+    await configureSO();
+
+    // Only allow exercising in the last part of the option life.
+    const scheduleTx = new Transaction();
+    scheduleTx.add(await program.methods
+      .setExerciseSchedule(new BN(0), [
+        { start: new BN(optionExpiration - 10), end: new BN(optionExpiration) },
+      ])
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+      })
+      .instruction());
+    await provider.sendAndConfirm(scheduleTx);
+
+    const stateObj = await program.account.state.fetch(state);
+    assert.equal(stateObj.exerciseWindows.length, 1);
+
+    await initStrike(STRIKE);
+    await issue(OPTIONS_AMOUNT, STRIKE);
+
+    let exercised = false;
+    try {
+      await exercise(OPTIONS_AMOUNT / LOT_SIZE);
+      exercised = true;
+    } catch (err) {
+      console.log('Exercise failed as expected');
+    }
+    assert(!exercised);
+
+    // Quotes are gated the same way, so they do not price an exercise that
+    // would fail.
+    let quoted = false;
+    try {
+      await program.methods
+        .quoteExercise(new BN(1), new BN(STRIKE), provider.wallet.publicKey)
+        .accounts({
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          feeSchedule: await feeSchedulePda(),
+        })
+        .view();
+      quoted = true;
+    } catch (err) {
+      console.log('Quote failed as expected');
+    }
+    assert(!quoted);
+
+    // The schedule is fixed once options are issued.
+    let rescheduled = false;
+    try {
+      const rescheduleTx = new Transaction();
+      rescheduleTx.add(await program.methods
+        .setExerciseSchedule(new BN(0), [])
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+        })
+        .instruction());
+      await provider.sendAndConfirm(rescheduleTx);
+      rescheduled = true;
+    } catch (err) {
+      console.log('Reschedule failed as expected');
+    }
+    assert(!rescheduled);
+  });
+
+  async function vestingGrantPda(strike: number, recipient: PublicKey) {
+    const [grant] = await PublicKey.findProgramAddress(
+      [
+        Buffer.from('so-grant'),
+        state.toBuffer(),
+        new BN(strike).toArrayLike(Buffer, 'be', 8),
+        recipient.toBuffer(),
+      ],
+      program.programId,
+    );
+    return grant;
+  }
+
+  async function createGrant(recipient: PublicKey, lots: number, start: number, cliff: number, end: number) {
+    const instr = await program.methods
+      .createGrant(new BN(STRIKE), new BN(lots), new BN(start), new BN(cliff), new BN(end))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        payer: provider.wallet.publicKey,
+        state,
+        strikeState: await strikeStatePda(STRIKE),
+        recipient,
+        vestingGrant: await vestingGrantPda(STRIKE, recipient),
+      })
+      .instruction();
+    const tx = new Transaction();
+    tx.add(instr);
+    await provider.sendAndConfirm(tx);
+  }
+
+  it('Vesting Grant', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+      const now = Math.floor(Date.now() / 1_000);
+      const lots = 5;
+
+      // Fully vested grant to the wallet.
+      await createGrant(provider.wallet.publicKey, lots, now - 20, now - 20, now - 10);
+
+      // Granted lots lock the terms of the strike like issued ones.
+      const strikeStateObj = await program.account.strikeState.fetch(await strikeStatePda(STRIKE));
+      assert.equal(strikeStateObj.lotsIssued.toNumber(), 0);
+      assert.equal(strikeStateObj.lotsGranted.toNumber(), lots);
+      let capped = false;
+      try {
+        const capTx = new Transaction();
+        capTx.add(await program.methods
+          .setStrikeCap(new BN(STRIKE), new BN(2 * STRIKE))
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state,
+            strikeState: await strikeStatePda(STRIKE),
+          })
+          .instruction());
+        await provider.sendAndConfirm(capTx);
+        capped = true;
+      } catch (err) {
+        console.log('Set strike cap failed as expected');
+      }
+      assert(!capped);
+
+      userSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      const claimTx = new Transaction();
+      claimTx.add(await program.methods
+        .claimVested(new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          vestingGrant: await vestingGrantPda(STRIKE, provider.wallet.publicKey),
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          userSoAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(claimTx);
+      assert.equal(
+        Number((await getAccount(provider.connection, userSoAccount)).amount),
+        lots,
+      );
+
+      // Grant that has not started vesting gets all collateral back when
+      // revoked.
+      const recipient = Keypair.generate().publicKey;
+      const availableBefore = (await program.account.state.fetch(state)).optionsAvailable.toNumber();
+      await createGrant(recipient, lots, now + 10, now + 20, optionExpiration);
+      assert.equal(
+        (await program.account.state.fetch(state)).optionsAvailable.toNumber(),
+        availableBefore - lots * LOT_SIZE,
+      );
+      const revokeTx = new Transaction();
+      revokeTx.add(await program.methods
+        .revokeGrant(new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          vestingGrant: await vestingGrantPda(STRIKE, recipient),
+          strikeState: await strikeStatePda(STRIKE),
+        })
+        .instruction());
+      await provider.sendAndConfirm(revokeTx);
+      assert.equal(
+        (await program.account.state.fetch(state)).optionsAvailable.toNumber(),
+        availableBefore,
+      );
+      const grant = await program.account.vestingGrant.fetch(await vestingGrantPda(STRIKE, recipient));
+      assert.equal(grant.totalLots.toNumber(), 0);
+      assert.equal(
+        (await program.account.strikeState.fetch(await strikeStatePda(STRIKE))).lotsGranted.toNumber(),
+        lots,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Auto Exercise', async () => {
+    try {
+      await configureSO();
+      const oracle = new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8');
+      const oracleTx = new Transaction();
+      oracleTx.add(await program.methods
+        .setOracle(new BN('18446744073709551615'))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          oracle,
+        })
+        .instruction());
+      await provider.sendAndConfirm(oracleTx);
+
+      await initStrike(STRIKE);
+      const lots = 3;
+      await issue(lots * LOT_SIZE, STRIKE);
+
+      userQuoteAccount = await createTokenAccount(
+        provider,
+        quoteMint,
+        provider.wallet.publicKey,
+      );
+      await mintToAccount(
+        provider,
+        quoteMint,
+        userQuoteAccount,
+        new BN(lots * STRIKE),
+        provider.wallet.publicKey,
+      );
+      userBaseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+
+      const [autoExercise] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-auto-exercise'),
+          state.toBuffer(),
+          new BN(STRIKE).toArrayLike(Buffer, 'be', 8),
+          provider.wallet.publicKey.toBuffer(),
+        ],
+        program.programId,
+      );
+      const registerTx = new Transaction();
+      registerTx.add(await program.methods
+        .registerAutoExercise(new BN(STRIKE), new BN(lots))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          autoExercise,
+          strikeState: await strikeStatePda(STRIKE),
+          userSoAccount,
+          userQuoteAccount,
+          userBaseAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(registerTx);
+
+      // The quote account is approved to the first registration, so another
+      // strike can not be registered with it.
+      const firstOptionMint = optionMint;
+      await initStrike(2 * STRIKE);
+      const secondSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      optionMint = firstOptionMint;
+      const [secondAutoExercise] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-auto-exercise'),
+          state.toBuffer(),
+          new BN(2 * STRIKE).toArrayLike(Buffer, 'be', 8),
+          provider.wallet.publicKey.toBuffer(),
+        ],
+        program.programId,
+      );
+      let registeredTwice = false;
+      try {
+        const secondRegisterTx = new Transaction();
+        secondRegisterTx.add(await program.methods
+          .registerAutoExercise(new BN(2 * STRIKE), new BN(1))
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state,
+            autoExercise: secondAutoExercise,
+            strikeState: await strikeStatePda(2 * STRIKE),
+            userSoAccount: secondSoAccount,
+            userQuoteAccount,
+            userBaseAccount,
+          })
+          .instruction());
+        await provider.sendAndConfirm(secondRegisterTx);
+        registeredTwice = true;
+      } catch (err) {
+        console.log('Second registration failed as expected');
+      }
+      assert(!registeredTwice);
+
+      // Expiration is within the crank window, so anyone can crank now.
+      const crankTx = new Transaction();
+      crankTx.add(await program.methods
+        .crankAutoExercise(new BN(STRIKE))
+        .accounts({
+          state,
+          autoExercise,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          oracle,
+          quoteMint,
+          userQuoteAccount,
+          projectQuoteAccount: quoteAccount,
+          feeQuoteAccount: await SO.getFeeAccount(quoteMint),
+          feeSchedule: await feeSchedulePda(),
+          baseVault,
+          userBaseAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(crankTx);
+
+      assert.equal(
+        Number((await getAccount(provider.connection, userBaseAccount)).amount),
+        lots * LOT_SIZE,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, userSoAccount)).amount),
+        0,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Auto Exercise Fail Below Fee', async () => {
+    await configureSO();
+    const oracle = new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8');
+    const oracleTx = new Transaction();
+    oracleTx.add(await program.methods
+      .setOracle(new BN('18446744073709551615'))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+        oracle,
+      })
+      .instruction());
+    await provider.sendAndConfirm(oracleTx);
+
+    // The oracle price per lot is 2 * LOT_SIZE, which is above this strike
+    // but below the strike plus the 3.5% fee.
+    const strike = 1_950_000;
+    await initStrike(strike);
+    const lots = 1;
+    await issue(lots * LOT_SIZE, strike);
+
+    userQuoteAccount = await createTokenAccount(
+      provider,
+      quoteMint,
+      provider.wallet.publicKey,
+    );
+    await mintToAccount(
+      provider,
+      quoteMint,
+      userQuoteAccount,
+      new BN(lots * strike),
+      provider.wallet.publicKey,
+    );
+    userBaseAccount = await createTokenAccount(
+      provider,
+      baseMint,
+      provider.wallet.publicKey,
+    );
+
+    const [autoExercise] = await PublicKey.findProgramAddress(
+      [
+        Buffer.from('so-auto-exercise'),
+        state.toBuffer(),
+        new BN(strike).toArrayLike(Buffer, 'be', 8),
+        provider.wallet.publicKey.toBuffer(),
+      ],
+      program.programId,
+    );
+    const registerTx = new Transaction();
+    registerTx.add(await program.methods
+      .registerAutoExercise(new BN(strike), new BN(lots))
+      .accounts({
+        authority: provider.wallet.publicKey,
+        state,
+        autoExercise,
+        strikeState: await strikeStatePda(strike),
+        userSoAccount,
+        userQuoteAccount,
+        userBaseAccount,
+      })
+      .instruction());
+    await provider.sendAndConfirm(registerTx);
+
+    let cranked = false;
+    try {
+      const crankTx = new Transaction();
+      crankTx.add(await program.methods
+        .crankAutoExercise(new BN(strike))
+        .accounts({
+          state,
+          autoExercise,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(strike),
+          oracle,
+          quoteMint,
+          userQuoteAccount,
+          projectQuoteAccount: quoteAccount,
+          feeQuoteAccount: await createFeeAccount(),
+          feeSchedule: await feeSchedulePda(),
+          baseVault,
+          userBaseAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(crankTx);
+      cranked = true;
+    } catch (err) {
+      console.log('Crank below the fee failed as expected');
+    }
+    assert(!cranked);
+    assert.equal(
+      Number((await getAccount(provider.connection, userSoAccount)).amount),
+      lots,
+    );
+  });
+
+  it('Strike Schedule', async () => {
+    try {
+      await configureSO();
+
+      // Schedules need a strike that is not reversible.
+      await initStrikeNotReversible(STRIKE);
+
+      // Strike already stepped up to double.
+      const now = Math.floor(Date.now() / 1_000);
+      const scheduleTx = new Transaction();
+      scheduleTx.add(await program.methods
+        .setStrikeSchedule(new BN(STRIKE), { step: {} }, [
+          { timestamp: new BN(now - 10), strike: new BN(2 * STRIKE) },
+        ])
+        .accounts({
+          authority: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+        })
+        .instruction());
+      await provider.sendAndConfirm(scheduleTx);
+
+      const lots = 10;
+      await issue(lots * LOT_SIZE, STRIKE);
+      const quoteBefore = Number((await getAccount(provider.connection, quoteAccount)).amount);
+      await exercise(lots);
+
+      const payment = lots * 2 * STRIKE;
+      assert.equal(
+        Number((await getAccount(provider.connection, quoteAccount)).amount) - quoteBefore,
+        payment - Math.floor((payment * 350) / 10_000),
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Capped Cash Exercise', async () => {
+    try {
+      await configureSO();
+      const oracle = new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8');
+      const oracleTx = new Transaction();
+      oracleTx.add(await program.methods
+        .setOracle(new BN('18446744073709551615'))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          oracle,
+        })
+        .instruction());
+      await provider.sendAndConfirm(oracleTx);
+
+      await initStrikeNotReversible(STRIKE);
+      const cap = 2 * STRIKE;
+      const capTx = new Transaction();
+      capTx.add(await program.methods
+        .setStrikeCap(new BN(STRIKE), new BN(cap))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+        })
+        .instruction());
+      await provider.sendAndConfirm(capTx);
+
+      const lots = 2;
+      await issue(lots * LOT_SIZE, STRIKE);
+      userBaseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+      const exerciseTx = new Transaction();
+      exerciseTx.add(await program.methods
+        .exerciseCash(new BN(lots), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          oracle,
+          quoteMint,
+          baseVault,
+          userBaseAccount,
+          feeBaseAccount: await SO.getFeeAccount(baseMint),
+          feeSchedule: await feeSchedulePda(),
+        })
+        .instruction());
+
+      // The DAO needs a base fee account for the new base mint.
+      const ataTx = new Transaction();
+      ataTx.add(
+        await createAssociatedTokenAccount(
+          provider.wallet.publicKey,
+          new PublicKey('7Z36Efbt7a4nLiV7s5bY7J2e4TJ6V9JEKGccsy2od2bE'),
+          baseMint,
+        ),
+      );
+      await provider.sendAndConfirm(ataTx);
+      await provider.sendAndConfirm(exerciseTx);
+
+      // The oracle price is far above the cap, so the payoff is the cap.
+      const pricePerLot = 2 * LOT_SIZE;
+      const intrinsic = Math.floor((lots * LOT_SIZE * (cap - STRIKE)) / pricePerLot);
+      const fee = Math.floor((intrinsic * 350) / 10_000);
+      assert.equal(
+        Number((await getAccount(provider.connection, userBaseAccount)).amount),
+        intrinsic - fee,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Knock In Barrier', async () => {
+    try {
+      await configureSO();
+      const oracle = new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8');
+      const oracleTx = new Transaction();
+      oracleTx.add(await program.methods
+        .setOracle(new BN('18446744073709551615'))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          oracle,
+        })
+        .instruction());
+      await provider.sendAndConfirm(oracleTx);
+
+      await initStrike(STRIKE);
+
+      // Activates once the price is at least 1 quote per base.
+      const barrierTx = new Transaction();
+      barrierTx.add(await program.methods
+        .setBarrier(new BN(STRIKE), { upAndIn: {} }, new BN(LOT_SIZE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          barrierOracle: oracle,
+        })
+        .instruction());
+      await provider.sendAndConfirm(barrierTx);
+
+      const lots = 2;
+      await issue(lots * LOT_SIZE, STRIKE);
+
+      let exercised = false;
+      try {
+        await exercise(1);
+        exercised = true;
+      } catch (err) {
+        console.log('Exercise failed as expected');
+      }
+      assert(!exercised);
+
+      const triggerTx = new Transaction();
+      triggerTx.add(await program.methods
+        .triggerBarrier(new BN(STRIKE))
+        .accounts({
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          barrierOracle: oracle,
+          quoteMint,
+        })
+        .instruction());
+      await provider.sendAndConfirm(triggerTx);
+
+      await exercise(1);
+      assert.equal(
+        Number((await getAccount(provider.connection, userBaseAccount)).amount),
+        LOT_SIZE,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Net Exercise', async () => {
+    try {
+      await configureSO();
+      const oracle = new PublicKey('3yAzYGkPoo13CVFRWKY9QKENAHZsqDgcbXw7updKUDe8');
+      const haircutBps = 500;
+      const setupTx = new Transaction();
+      setupTx.add(await program.methods
+        .setOracle(new BN('18446744073709551615'))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          oracle,
+        })
+        .instruction());
+      setupTx.add(await program.methods
+        .setNetExerciseHaircut(haircutBps)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+        })
+        .instruction());
+      setupTx.add(
+        await createAssociatedTokenAccount(
+          provider.wallet.publicKey,
+          new PublicKey('7Z36Efbt7a4nLiV7s5bY7J2e4TJ6V9JEKGccsy2od2bE'),
+          baseMint,
+        ),
+      );
+      await provider.sendAndConfirm(setupTx);
+
+      await initStrike(STRIKE);
+      const lots = 1;
+      await issue(lots * LOT_SIZE, STRIKE);
+
+      // The haircut is fixed once options are issued.
+      let haircutChanged = false;
+      try {
+        const haircutTx = new Transaction();
+        haircutTx.add(await program.methods
+          .setNetExerciseHaircut(0)
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state,
+          })
+          .instruction());
+        await provider.sendAndConfirm(haircutTx);
+        haircutChanged = true;
+      } catch (err) {
+        console.log('Set haircut failed as expected');
+      }
+      assert(!haircutChanged);
+
+      userBaseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+      const feeBaseAccount = await SO.getFeeAccount(baseMint);
+      const projectBaseBefore = Number((await getAccount(provider.connection, baseAccount)).amount);
+      const exerciseTx = new Transaction();
+      exerciseTx.add(await program.methods
+        .netExercise(new BN(lots), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          oracle,
+          quoteMint,
+          baseVault,
+          userBaseAccount,
+          projectBaseAccount: baseAccount,
+          feeBaseAccount,
+          feeSchedule: await feeSchedulePda(),
+        })
+        .instruction());
+      await provider.sendAndConfirm(exerciseTx);
+
+      // Base is valued at 2 quote per base less the haircut.
+      const valuedPricePerLot = (2 * LOT_SIZE * (10_000 - haircutBps)) / 10_000;
+      const payment = lots * STRIKE;
+      const fee = Math.floor((payment * 350) / 10_000);
+      const projectBase = Math.ceil(((payment - fee) * LOT_SIZE) / valuedPricePerLot);
+      const feeBase = Math.floor((fee * LOT_SIZE) / valuedPricePerLot);
+      assert.equal(
+        Number((await getAccount(provider.connection, baseAccount)).amount) - projectBaseBefore,
+        projectBase,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, feeBaseAccount)).amount),
+        feeBase,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, userBaseAccount)).amount),
+        lots * LOT_SIZE - projectBase - feeBase,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Flash Exercise', async () => {
+    try {
+      const mockSwap: Program<MockSwap> = workspace.MockSwap as Program<MockSwap>;
+      await configureSO();
+      await initStrike(STRIKE);
+      const lots = 2;
+      await issue(lots * LOT_SIZE, STRIKE);
+
+      userBaseAccount = await createTokenAccount(
+        provider,
+        baseMint,
+        provider.wallet.publicKey,
+      );
+      userQuoteAccount = await createTokenAccount(
+        provider,
+        quoteMint,
+        provider.wallet.publicKey,
+      );
+
+      // Pool that buys the base for exactly the strike payment.
+      const [poolAuthority] = await PublicKey.findProgramAddress(
+        [Buffer.from('pool')],
+        mockSwap.programId,
+      );
+      const poolBase = await createTokenAccount(provider, baseMint, poolAuthority);
+      const poolQuote = await createTokenAccount(provider, quoteMint, poolAuthority);
+      await mintToAccount(
+        provider,
+        quoteMint,
+        poolQuote,
+        new BN(lots * STRIKE),
+        provider.wallet.publicKey,
+      );
+      const swapInstr = await mockSwap.methods
+        .swap(new BN(lots * LOT_SIZE), new BN(lots * STRIKE))
+        .accounts({
+          user: provider.wallet.publicKey,
+          userSource: userBaseAccount,
+          userDestination: userQuoteAccount,
+          poolSource: poolBase,
+          poolDestination: poolQuote,
+          poolAuthority,
+        })
+        .instruction();
+
+      const [swapWhitelistEntry] = await PublicKey.findProgramAddress(
+        [Buffer.from('swap-whitelist'), mockSwap.programId.toBuffer()],
+        program.programId,
+      );
+      const exerciseTx = new Transaction();
+      exerciseTx.add(await program.methods
+        .flashExercise(new BN(lots), new BN(STRIKE), swapInstr.data)
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          userSoAccount,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          userQuoteAccount,
+          projectQuoteAccount: quoteAccount,
+          feeQuoteAccount: await SO.getFeeAccount(quoteMint),
+          feeSchedule: await feeSchedulePda(),
+          baseVault,
+          userBaseAccount,
+          swapProgram: mockSwap.programId,
+          swapWhitelistEntry,
+        })
+        .remainingAccounts(swapInstr.keys)
+        .instruction());
+      await provider.sendAndConfirm(exerciseTx);
+
+      // All the base was sold and all the quote went to the payment.
+      assert.equal(
+        Number((await getAccount(provider.connection, poolBase)).amount),
+        lots * LOT_SIZE,
+      );
+      assert.equal(
+        Number((await getAccount(provider.connection, userQuoteAccount)).amount),
+        0,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Dutch Auction', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      const now = Math.floor(Date.now() / 1_000);
+      const [auction] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-auction'),
+          state.toBuffer(),
+          new BN(STRIKE).toArrayLike(Buffer, 'be', 8),
+        ],
+        program.programId,
+      );
+      const startPrice = 10 * STRIKE;
+      const createTx = new Transaction();
+      createTx.add(await program.methods
+        .createAuction(new BN(STRIKE), {
+          lotsForSale: new BN(5),
+          startTime: new BN(now - 10),
+          endTime: new BN(subscriptionPeriodEnd),
+          startPrice: new BN(startPrice),
+          floorPrice: new BN(STRIKE),
+          decay: { linear: {} },
+        })
+        .accounts({
+          authority: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          auction,
+        })
+        .instruction());
+      await provider.sendAndConfirm(createTx);
+
+      const lots = 2;
+      userSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      userQuoteAccount = await createTokenAccount(
+        provider,
+        quoteMint,
+        provider.wallet.publicKey,
+      );
+      await mintToAccount(
+        provider,
+        quoteMint,
+        userQuoteAccount,
+        new BN(lots * startPrice),
+        provider.wallet.publicKey,
+      );
+      const buyTx = new Transaction();
+      buyTx.add(await program.methods
+        .buyFromAuction(new BN(STRIKE), new BN(lots), new BN(startPrice))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          auction,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          buyerSoAccount: userSoAccount,
+          buyerQuoteAccount: userQuoteAccount,
+          projectQuoteAccount: quoteAccount,
+          feeQuoteAccount: await SO.getFeeAccount(quoteMint),
+          feeSchedule: await feeSchedulePda(),
+        })
+        .instruction());
+      await provider.sendAndConfirm(buyTx);
+
+      assert.equal(
+        Number((await getAccount(provider.connection, userSoAccount)).amount),
+        lots,
+      );
+      // The price has decayed a little since the start.
+      const paid = lots * startPrice
+        - Number((await getAccount(provider.connection, userQuoteAccount)).amount);
+      assert(paid <= lots * startPrice && paid >= lots * STRIKE);
+      assert.equal((await program.account.auction.fetch(auction)).lotsSold.toNumber(), lots);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Option Shop', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      const [shop] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-shop'),
+          state.toBuffer(),
+          new BN(STRIKE).toArrayLike(Buffer, 'be', 8),
+        ],
+        program.programId,
+      );
+      const premium = STRIKE / 10;
+      const createTx = new Transaction();
+      createTx.add(await program.methods
+        .createShop(new BN(STRIKE), new BN(premium), new BN(5))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          shop,
+        })
+        .instruction());
+      await provider.sendAndConfirm(createTx);
+
+      // Lots reserved for the shop lock the terms before any are sold.
+      const strikeStateObj = await program.account.strikeState.fetch(await strikeStatePda(STRIKE));
+      assert.equal(strikeStateObj.lotsIssued.toNumber(), 0);
+      assert.equal(strikeStateObj.lotsReserved.toNumber(), 5);
+      let capped = false;
+      try {
+        const capTx = new Transaction();
+        capTx.add(await program.methods
+          .setStrikeCap(new BN(STRIKE), new BN(2 * STRIKE))
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state,
+            strikeState: await strikeStatePda(STRIKE),
+          })
+          .instruction());
+        await provider.sendAndConfirm(capTx);
+        capped = true;
+      } catch (err) {
+        console.log('Set strike cap failed as expected');
+      }
+      assert(!capped);
+
+      const lots = 3;
+      userSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      userQuoteAccount = await createTokenAccount(
+        provider,
+        quoteMint,
+        provider.wallet.publicKey,
+      );
+      await mintToAccount(
+        provider,
+        quoteMint,
+        userQuoteAccount,
+        new BN(lots * premium),
+        provider.wallet.publicKey,
+      );
+      const buyTx = new Transaction();
+      buyTx.add(await program.methods
+        .buyFromShop(new BN(STRIKE), new BN(lots), new BN(premium))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          shop,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          buyerSoAccount: userSoAccount,
+          buyerQuoteAccount: userQuoteAccount,
+          projectQuoteAccount: quoteAccount,
+          feeQuoteAccount: await SO.getFeeAccount(quoteMint),
+          feeSchedule: await feeSchedulePda(),
+          eventAuthority: await eventAuthorityPda(),
+          program: program.programId,
+        })
+        .instruction());
+      const buySignature = await provider.sendAndConfirm(buyTx, [], { commitment: 'confirmed' });
+
+      assert.equal(
+        Number((await getAccount(provider.connection, userSoAccount)).amount),
+        lots,
+      );
+
+      // Sales record the same event as issue.
+      const event = await selfCpiEvent(buySignature);
+      assert.equal(event.name, 'OptionsIssued');
+      assert.equal(event.data.authority.toBase58(), provider.wallet.publicKey.toBase58());
+      assert.equal(event.data.amountLots.toNumber(), lots);
+      assert.equal(event.data.recipient.toBase58(), userSoAccount.toBase58());
+      assert.equal(
+        Number((await getAccount(provider.connection, userQuoteAccount)).amount),
+        0,
+      );
+      assert.equal((await program.account.shop.fetch(shop)).lotsSold.toNumber(), lots);
+
+      // Reprice the remaining lots.
+      const newPremium = premium * 2;
+      const repriceTx = new Transaction();
+      repriceTx.add(await program.methods
+        .setShopPremium(new BN(STRIKE), new BN(newPremium))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          shop,
+        })
+        .instruction());
+      await provider.sendAndConfirm(repriceTx);
+      assert.equal((await program.account.shop.fetch(shop)).premiumPerLot.toNumber(), newPremium);
+
+      // A buy at the old premium is rejected instead of paying the new one.
+      await mintToAccount(
+        provider,
+        quoteMint,
+        userQuoteAccount,
+        new BN(newPremium),
+        provider.wallet.publicKey,
+      );
+      let boughtAtOldPremium = false;
+      try {
+        const staleBuyTx = new Transaction();
+        staleBuyTx.add(await program.methods
+          .buyFromShop(new BN(STRIKE), new BN(1), new BN(premium))
+          .accounts({
+            authority: provider.wallet.publicKey,
+            state,
+            shop,
+            optionMint,
+            strikeState: await strikeStatePda(STRIKE),
+            buyerSoAccount: userSoAccount,
+            buyerQuoteAccount: userQuoteAccount,
+            projectQuoteAccount: quoteAccount,
+            feeQuoteAccount: await SO.getFeeAccount(quoteMint),
+            feeSchedule: await feeSchedulePda(),
+          })
+          .instruction());
+        await provider.sendAndConfirm(staleBuyTx);
+        boughtAtOldPremium = true;
+      } catch (err) {
+        console.log('Buy at old premium failed as expected');
+      }
+      assert(!boughtAtOldPremium);
+      assert.equal(
+        Number((await getAccount(provider.connection, userQuoteAccount)).amount),
+        newPremium,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Merkle Claim', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      const [merkleDistributor] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-merkle'),
+          state.toBuffer(),
+          new BN(STRIKE).toArrayLike(Buffer, 'be', 8),
+        ],
+        program.programId,
+      );
+
+      // Two leaf tree where the wallet is leaf 0.
+      const leaf = (index: number, claimant: PublicKey, lots: number) => Buffer.from(
+        keccak256.arrayBuffer(Buffer.concat([
+          new BN(index).toArrayLike(Buffer, 'le', 8),
+          claimant.toBuffer(),
+          new BN(lots).toArrayLike(Buffer, 'le', 8),
+        ])),
+      );
+      const lots = 3;
+      const leaf0 = leaf(0, provider.wallet.publicKey, lots);
+      const leaf1 = leaf(1, Keypair.generate().publicKey, 2);
+      const sorted = Buffer.compare(leaf0, leaf1) <= 0 ? [leaf0, leaf1] : [leaf1, leaf0];
+      const root = Buffer.from(keccak256.arrayBuffer(Buffer.concat(sorted)));
+
+      const setRootTx = new Transaction();
+      setRootTx.add(await program.methods
+        .setMerkleRoot(new BN(STRIKE), Array.from(root), new BN(2), new BN(5))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          merkleDistributor,
+        })
+        .instruction());
+      await provider.sendAndConfirm(setRootTx);
+
+      userSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      const claimIx = async () => program.methods
+        .claim(new BN(STRIKE), new BN(0), new BN(lots), [Array.from(leaf1)])
+        .accounts({
+          claimant: provider.wallet.publicKey,
+          state,
+          merkleDistributor,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          claimantSoAccount: userSoAccount,
+        })
+        .instruction();
+      const claimTx = new Transaction();
+      claimTx.add(await claimIx());
+      await provider.sendAndConfirm(claimTx);
+
+      assert.equal(
+        Number((await getAccount(provider.connection, userSoAccount)).amount),
+        lots,
+      );
+      assert.equal(
+        (await program.account.merkleDistributor.fetch(merkleDistributor)).lotsClaimed.toNumber(),
+        lots,
+      );
+
+      // Second claim of the same leaf fails.
+      let claimedTwice = false;
+      try {
+        const secondClaimTx = new Transaction();
+        secondClaimTx.add(await claimIx());
+        await provider.sendAndConfirm(secondClaimTx);
+        claimedTwice = true;
+      } catch (err) {
+        console.log('Second claim failed as expected');
+      }
+      assert(!claimedTwice);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Stake Pool', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      const [stakePool] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-stake-pool'),
+          state.toBuffer(),
+          new BN(STRIKE).toArrayLike(Buffer, 'be', 8),
+        ],
+        program.programId,
+      );
+      const [stakeVault] = await PublicKey.findProgramAddress(
+        [Buffer.from('so-stake-vault'), stakePool.toBuffer()],
+        program.programId,
+      );
+      const [stakeEntry] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from('so-stake-entry'),
+          stakePool.toBuffer(),
+          provider.wallet.publicKey.toBuffer(),
+        ],
+        program.programId,
+      );
+
+      const stakeMint = await createMint(provider, undefined);
+      const userStakeAccount = await createTokenAccount(
+        provider,
+        stakeMint,
+        provider.wallet.publicKey,
+      );
+      const stakeAmount = 1_000_000;
+      await mintToAccount(
+        provider,
+        stakeMint,
+        userStakeAccount,
+        new BN(stakeAmount),
+        provider.wallet.publicKey,
+      );
+
+      const totalLots = 5;
+      const createTx = new Transaction();
+      createTx.add(await program.methods
+        .createStakePool(new BN(STRIKE), new BN(totalLots))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          payer: provider.wallet.publicKey,
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          stakePool,
+          stakeMint,
+          stakeVault,
+        })
+        .instruction());
+      await provider.sendAndConfirm(createTx);
+
+      const stakeTx = new Transaction();
+      stakeTx.add(await program.methods
+        .stake(new BN(STRIKE), new BN(stakeAmount))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          stakePool,
+          stakeEntry,
+          stakeVault,
+          userStakeAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(stakeTx);
+      assert.equal(
+        Number((await getAccount(provider.connection, stakeVault)).amount),
+        stakeAmount,
+      );
+
+      // Options are only claimable after the subscription period.
+      await new Promise((r) => setTimeout(
+        r,
+        (subscriptionPeriodEnd + 2) * 1_000 - Date.now(),
+      ));
+
+      const closeIx = async () => program.methods
+        .closeStakePool(new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          stakePool,
+        })
+        .instruction();
+
+      // Cannot close while the staker has not claimed.
+      let closedEarly = false;
+      try {
+        const earlyCloseTx = new Transaction();
+        earlyCloseTx.add(await closeIx());
+        await provider.sendAndConfirm(earlyCloseTx);
+        closedEarly = true;
+      } catch (err) {
+        console.log('Close before claims failed as expected');
+      }
+      assert(!closedEarly);
+
+      userSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      const claimTx = new Transaction();
+      claimTx.add(await program.methods
+        .claimStakingOptions(new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          stakePool,
+          stakeEntry,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          userSoAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(claimTx);
+
+      // The only staker earns every lot.
+      assert.equal(
+        Number((await getAccount(provider.connection, userSoAccount)).amount),
+        totalLots,
+      );
+
+      // Everything was claimed, so closing returns no collateral.
+      const optionsAvailableBefore = (await program.account.state.fetch(state))
+        .optionsAvailable.toNumber();
+      const closeTx = new Transaction();
+      closeTx.add(await closeIx());
+      await provider.sendAndConfirm(closeTx);
+      const stakePoolObj = await program.account.stakePool.fetch(stakePool);
+      assert(stakePoolObj.closed);
+      assert.equal(
+        stakePoolObj.stakeSecondsClaimed.toString(),
+        stakePoolObj.stakeSeconds.toString(),
+      );
+      assert.equal(
+        (await program.account.state.fetch(state)).optionsAvailable.toNumber(),
+        optionsAvailableBefore,
+      );
+
+      // Stakers can still unstake from a closed pool, even once withdraw_all
+      // has closed the State.
+      await new Promise((r) => setTimeout(
+        r,
+        (optionExpiration + 2) * 1_000 - Date.now(),
+      ));
+      await withdrawAll();
+      assert.equal(await provider.connection.getAccountInfo(state), null);
+
+      const unstakeTx = new Transaction();
+      unstakeTx.add(await program.methods
+        .unstake(new BN(STRIKE), new BN(stakeAmount))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          stakePool,
+          stakeEntry,
+          stakeVault,
+          userStakeAccount,
+        })
+        .instruction());
+      await provider.sendAndConfirm(unstakeTx);
+      assert.equal(
+        Number((await getAccount(provider.connection, userStakeAccount)).amount),
+        stakeAmount,
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Events', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      userSoAccount = await createTokenAccount(
+        provider,
+        optionMint,
+        provider.wallet.publicKey,
+      );
+      const lots = OPTIONS_AMOUNT / LOT_SIZE;
+      const issueTx = new Transaction();
+      issueTx.add(await program.methods
+        .issue(new BN(OPTIONS_AMOUNT), new BN(STRIKE))
+        .accounts({
+          authority: provider.wallet.publicKey,
+          state,
+          optionMint,
+          strikeState: await strikeStatePda(STRIKE),
+          userSoAccount,
+          eventAuthority: await eventAuthorityPda(),
+          program: program.programId,
+        })
+        .instruction());
+      const signature = await provider.sendAndConfirm(issueTx, [], { commitment: 'confirmed' });
+
+      const event = await selfCpiEvent(signature);
+      assert.equal(event.name, 'OptionsIssued');
+      assert.equal(event.data.strike.toNumber(), STRIKE);
+      assert.equal(event.data.amountLots.toNumber(), lots);
+      assert.equal(event.data.recipient.toBase58(), userSoAccount.toBase58());
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Quote Exercise', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      const lots = 3;
+      const quote = await program.methods
+        .quoteExercise(new BN(lots), new BN(STRIKE), provider.wallet.publicKey)
+        .accounts({
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+          feeSchedule: await feeSchedulePda(),
+        })
+        .view();
+
+      assert.equal(quote.payment.toNumber(), lots * STRIKE);
+      assert.equal(quote.baseDelivered.toNumber(), lots * LOT_SIZE);
+      assert.equal(
+        quote.fee.toNumber(),
+        Math.floor((lots * STRIKE * quote.feeBps.toNumber()) / 10_000),
+      );
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+  });
+
+  it('Quote Reverse Exercise', async () => {
+    try {
+      await configureSO();
+      await initStrike(STRIKE);
+
+      const lots = 3;
+      const quote = await program.methods
+        .quoteReverseExercise(new BN(lots), new BN(STRIKE))
+        .accounts({
+          state,
+          strikeState: await strikeStatePda(STRIKE),
+        })
+        .view();
+
+      assert.equal(quote.payment.toNumber(), lots * STRIKE);
+      assert.equal(quote.baseDelivered.toNumber(), lots * LOT_SIZE);
+      assert.equal(quote.fee.toNumber(), 0);
+    } catch (err) {
+      console.log(err);
+      assert(false);
+    }
+
+    // Gated like reverse_exercise, so a strike without a reverse mint does
+    // not get a quote.
+    const notReversibleStrike = STRIKE * 2;
+    await initStrikeNotReversible(notReversibleStrike);
+    let quoted = false;
+    try {
+      await program.methods
+        .quoteReverseExercise(new BN(1), new BN(notReversibleStrike))
+        .accounts({
+          state,
+          strikeState: await strikeStatePda(notReversibleStrike),
+        })
+        .view();
+      quoted = true;
+    } catch (err) {
+      console.log('Quote failed as expected');
+    }
+    assert(!quoted);
+  });
+
   // TODO: Test fee exempt and reduced fee pairs
 });